@@ -0,0 +1,210 @@
+//! Incremental re-lexing of an edited R program.
+//!
+//! Editors send small edits to a large buffer. Instead of tokenizing the whole
+//! buffer again, [`relex`] reuses the tokens before the line of the edit,
+//! tokenizes the edited lines and reuses the tokens after the first unchanged
+//! newline following the edit.
+
+use std::ops::Range;
+
+use crate::tokenizer::Tokenizer;
+use crate::tokens::{CommentedToken, Token};
+
+/// A replacement of a byte range of the source with new text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextEdit<'a> {
+    /// The replaced byte range of the old source.
+    pub range: Range<usize>,
+    /// The text inserted in place of the range.
+    pub text: &'a str,
+}
+
+impl<'a> TextEdit<'a> {
+    pub fn new(range: Range<usize>, text: &'a str) -> Self {
+        Self { range, text }
+    }
+
+    /// Returns the source after applying this edit.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use tergo_tokenizer::incremental::TextEdit;
+    ///
+    /// let edit = TextEdit::new(5..6, "10");
+    /// assert_eq!(edit.apply("a <- 1\n"), "a <- 10\n");
+    /// ```
+    pub fn apply(&self, source: &str) -> String {
+        let mut edited = String::with_capacity(source.len() + self.text.len());
+        edited.push_str(&source[..self.range.start]);
+        edited.push_str(self.text);
+        edited.push_str(&source[self.range.end..]);
+        edited
+    }
+
+    /// The end of the inserted text in the new source.
+    pub fn new_end(&self) -> usize {
+        self.range.start + self.text.len()
+    }
+
+    /// Maps an offset of the old source that lies outside of the replaced range
+    /// to the corresponding offset in the new source.
+    pub fn map_offset(&self, offset: usize) -> Option<usize> {
+        if offset < self.range.start {
+            Some(offset)
+        } else if offset >= self.range.end {
+            Some(offset - self.range.end + self.new_end())
+        } else {
+            None
+        }
+    }
+}
+
+/// Returns the tokens of `new_source`, which is the old source with `edit` applied.
+///
+/// `old_tokens` are the tokens of the old source as returned by
+/// [`Tokenizer::tokenize`]. The result is the same as tokenizing `new_source`
+/// from scratch.
+///
+/// # Examples
+///
+/// ```
+/// use tergo_tokenizer::incremental::{relex, TextEdit};
+/// use tergo_tokenizer::{Token, Tokenizer};
+///
+/// let source = "a <- 1\nb <- 2\n";
+/// let old_tokens = Tokenizer::new(source).tokenize().unwrap();
+/// let edit = TextEdit::new(12..13, "3");
+/// let new_source = edit.apply(source);
+/// let tokens = relex(&old_tokens, &edit, &new_source).unwrap();
+/// assert_eq!(tokens[6].token, Token::Literal("3"));
+/// ```
+pub fn relex<'a>(
+    old_tokens: &[CommentedToken<'_>],
+    edit: &TextEdit<'_>,
+    new_source: &'a str,
+) -> Result<Vec<CommentedToken<'a>>, crate::Error> {
+    // Tokenizing restarts after the last newline before the edit. The tokenizer
+    // starts from a clean state after every newline.
+    let restart_index = old_tokens
+        .iter()
+        .rposition(|token| {
+            matches!(token.token, Token::Newline)
+                && token.offset < edit.range.start
+                && new_source.as_bytes().get(token.offset) == Some(&b'\n')
+        })
+        .map_or(0, |index| index + 1);
+    let restart = match restart_index {
+        0 => 0,
+        index => old_tokens[index - 1].offset + 1,
+    };
+    let mut tokens = old_tokens[..restart_index]
+        .iter()
+        .map(|token| rebase(token, token.offset, new_source))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    // Once the tokenizer reaches a newline after the edit that is also a token
+    // of the old source, the remaining tokens did not change.
+    for token in Tokenizer::new(&new_source[restart..]) {
+        let mut token = token?;
+        token.offset += restart;
+        let is_newline = matches!(token.token, Token::Newline)
+            && new_source.as_bytes().get(token.offset) == Some(&b'\n');
+        if is_newline && token.offset >= edit.new_end() {
+            let old_newline = token.offset - edit.new_end() + edit.range.end;
+            if let Ok(index) = old_tokens.binary_search_by_key(&old_newline, |token| token.offset) {
+                if matches!(old_tokens[index].token, Token::Newline) {
+                    tokens.push(token);
+                    for token in &old_tokens[index + 1..] {
+                        let offset = edit
+                            .map_offset(token.offset)
+                            .expect("Tokens after the edit are outside of the replaced range");
+                        tokens.push(rebase(token, offset, new_source)?);
+                    }
+                    return Ok(tokens);
+                }
+            }
+        }
+        tokens.push(token);
+    }
+    Ok(tokens)
+}
+
+/// Moves a token of the old source to `offset` in the new source.
+fn rebase<'a>(
+    token: &CommentedToken<'_>,
+    offset: usize,
+    new_source: &'a str,
+) -> Result<CommentedToken<'a>, crate::Error> {
+    let text = |old: &str| -> Result<&'a str, crate::Error> {
+        match new_source.get(offset..offset + old.len()) {
+            Some(text) if text == old => Ok(text),
+            _ => match old {
+                "TRUE" => Ok("TRUE"),
+                "FALSE" => Ok("FALSE"),
+                _ => Err(crate::Error::UnexpectedCharacter(format!(
+                    "Token {old} is not at offset {offset} of the edited source"
+                ))),
+            },
+        }
+    };
+    let token = match &token.token {
+        Token::Symbol(old) => Token::Symbol(text(old)?),
+        Token::Literal(old) => Token::Literal(text(old)?),
+        Token::Special(old) => Token::Special(text(old)?),
+        Token::InlineComment(old) => Token::InlineComment(text(old)?),
+        Token::Comment(old) => Token::Comment(text(old)?),
+        Token::Semicolon => Token::Semicolon,
+        Token::Newline => Token::Newline,
+        Token::LParen => Token::LParen,
+        Token::RParen => Token::RParen,
+        Token::LBrace => Token::LBrace,
+        Token::RBrace => Token::RBrace,
+        Token::LBracket => Token::LBracket,
+        Token::RBracket => Token::RBracket,
+        Token::Comma => Token::Comma,
+        Token::Continue => Token::Continue,
+        Token::Break => Token::Break,
+        Token::Stop => Token::Stop,
+        Token::If => Token::If,
+        Token::Else => Token::Else,
+        Token::While => Token::While,
+        Token::For => Token::For,
+        Token::Repeat => Token::Repeat,
+        Token::In => Token::In,
+        Token::Function => Token::Function,
+        Token::Lambda => Token::Lambda,
+        Token::LAssign => Token::LAssign,
+        Token::SuperAssign => Token::SuperAssign,
+        Token::ColonAssign => Token::ColonAssign,
+        Token::RAssign => Token::RAssign,
+        Token::OldAssign => Token::OldAssign,
+        Token::Equal => Token::Equal,
+        Token::NotEqual => Token::NotEqual,
+        Token::LowerThan => Token::LowerThan,
+        Token::GreaterThan => Token::GreaterThan,
+        Token::LowerEqual => Token::LowerEqual,
+        Token::GreaterEqual => Token::GreaterEqual,
+        Token::Power => Token::Power,
+        Token::Divide => Token::Divide,
+        Token::Multiply => Token::Multiply,
+        Token::Minus => Token::Minus,
+        Token::Plus => Token::Plus,
+        Token::Help => Token::Help,
+        Token::And => Token::And,
+        Token::VectorizedAnd => Token::VectorizedAnd,
+        Token::Or => Token::Or,
+        Token::VectorizedOr => Token::VectorizedOr,
+        Token::Dollar => Token::Dollar,
+        Token::Pipe => Token::Pipe,
+        Token::Modulo => Token::Modulo,
+        Token::NsGet => Token::NsGet,
+        Token::NsGetInt => Token::NsGetInt,
+        Token::Tilde => Token::Tilde,
+        Token::Colon => Token::Colon,
+        Token::Slot => Token::Slot,
+        Token::UnaryNot => Token::UnaryNot,
+        Token::EOF => Token::EOF,
+    };
+    Ok(CommentedToken::new(token, offset))
+}
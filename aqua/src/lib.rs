@@ -1,3 +1,4 @@
+pub mod incremental;
 pub mod tokenizer;
 pub mod tokens;
 pub use tokenizer::Tokenizer;
@@ -10,17 +10,24 @@ use crate::tokens::{
 /// Tokenizer for an R program.
 ///
 /// Transforms an R program into an array of language tokens.
+/// The tokenizer is also an iterator yielding the tokens one by one,
+/// which does not keep all of them in memory.
 pub struct Tokenizer<'a> {
     offset: usize,
     it: usize,
     current_char: char,
     source: CharIndices<'a>,
     raw_source: &'a str,
+    /// The token produced by the last step of tokenization.
+    pending: Option<CommentedToken<'a>>,
+    /// Whether the previous token is a newline or there is no previous token.
+    after_newline: bool,
+    finished: bool,
 }
 
-const SYMBOL_ENDING: [char; 29] = [
+const SYMBOL_ENDING: [char; 33] = [
     ' ', '(', ')', '{', '}', '#', ';', '\n', '\t', '\r', '+', '-', '/', '\\', '%', '*', '^', '!',
-    '&', '|', '<', '>', '=', ',', '[', ']', '$', '`', '"',
+    '&', '|', '<', '>', '=', ',', '[', ']', '$', '`', '"', ':', '@', '~', '?',
 ];
 impl<'a> Tokenizer<'a> {
     /// Returns a new tokenizer from an R program.
@@ -44,13 +51,18 @@ impl<'a> Tokenizer<'a> {
     /// ```
     ///
     pub fn new(input: &'a str) -> Self {
-        Self {
+        let mut tokenizer = Self {
             offset: 0,
             it: 0,
             current_char: '\0',
             source: input.char_indices(),
             raw_source: input,
-        }
+            pending: None,
+            after_newline: true,
+            finished: false,
+        };
+        tokenizer.advance();
+        tokenizer
     }
 
     /// Returns an array of tokens.
@@ -69,311 +81,312 @@ impl<'a> Tokenizer<'a> {
     /// println!("{tokens:?}");
     /// ```
     ///
-    pub fn tokenize(&mut self) -> Result<Vec<CommentedToken>, crate::Error> {
-        let mut tokens = vec![];
-        self.next();
-        while self.it < self.raw_source.len() {
-            match self.current_char {
-                ' ' | '\t' => {
-                    self.next();
-                }
-                '\r' => {
-                    self.next();
-                    self.push_token(Newline, &mut tokens);
-                    self.next();
-                }
-                '\n' => {
-                    self.push_token(Newline, &mut tokens);
-                    self.next();
-                }
-                ';' => {
-                    self.push_token(Semicolon, &mut tokens);
-                    self.next();
-                }
-                ',' => {
-                    self.push_token(Comma, &mut tokens);
-                    self.next();
-                }
-                '(' => {
-                    self.push_token(LParen, &mut tokens);
-                    self.next();
-                }
-                ')' => {
-                    self.push_token(RParen, &mut tokens);
-                    self.next();
-                }
-                '{' => {
-                    self.push_token(LBrace, &mut tokens);
-                    self.next();
-                }
-                '}' => {
-                    self.push_token(RBrace, &mut tokens);
-                    self.next();
-                }
-                '[' => {
-                    self.push_token(LBracket, &mut tokens);
-                    self.next();
-                }
-                ']' => {
-                    self.push_token(RBracket, &mut tokens);
-                    self.next();
-                }
-                '\'' | '\"' => {
-                    self.string_literal(&mut tokens);
-                    self.next();
-                }
-                '*' => {
-                    let next_char = self.lookahead().expect("Script does not end on '*'");
-                    match next_char {
-                        // That's undocumented, but it actually works...
-                        '*' => {
-                            self.push_token(Power, &mut tokens);
-                            self.next();
-                        }
-                        _ => self.push_token(Multiply, &mut tokens),
+    pub fn tokenize(&mut self) -> Result<Vec<CommentedToken<'a>>, crate::Error> {
+        let tokens = self.by_ref().collect::<Result<Vec<_>, _>>()?;
+        trace!("Tokenized: {:?}", tokens);
+        Ok(tokens)
+    }
+
+    /// Advances past the next token and returns it.
+    ///
+    /// Returns `None` if the skipped characters do not form a token,
+    /// for example whitespace.
+    fn next_token(&mut self) -> Option<CommentedToken<'a>> {
+        match self.current_char {
+            ' ' | '\t' => {
+                self.advance();
+            }
+            '\r' => {
+                self.advance();
+                self.push_token(Newline);
+                self.advance();
+            }
+            '\n' => {
+                self.push_token(Newline);
+                self.advance();
+            }
+            ';' => {
+                self.push_token(Semicolon);
+                self.advance();
+            }
+            ',' => {
+                self.push_token(Comma);
+                self.advance();
+            }
+            '(' => {
+                self.push_token(LParen);
+                self.advance();
+            }
+            ')' => {
+                self.push_token(RParen);
+                self.advance();
+            }
+            '{' => {
+                self.push_token(LBrace);
+                self.advance();
+            }
+            '}' => {
+                self.push_token(RBrace);
+                self.advance();
+            }
+            '[' => {
+                self.push_token(LBracket);
+                self.advance();
+            }
+            ']' => {
+                self.push_token(RBracket);
+                self.advance();
+            }
+            '\'' | '\"' => {
+                self.string_literal();
+                self.advance();
+            }
+            '*' => {
+                match self.lookahead() {
+                    // That's undocumented, but it actually works...
+                    Some('*') => {
+                        self.push_token(Power);
+                        self.advance();
                     }
-                    self.next();
-                }
-                '/' => {
-                    self.push_token(Divide, &mut tokens);
-                    self.next();
+                    _ => self.push_token(Multiply),
                 }
-                '^' => {
-                    self.push_token(Power, &mut tokens);
-                    self.next();
-                }
-                '+' => {
-                    self.push_token(Plus, &mut tokens);
-                    self.next();
-                }
-                '?' => {
-                    self.push_token(Help, &mut tokens);
-                    self.next();
-                }
-                '<' => {
-                    let next_char = self.lookahead().expect("Script does not end on '<'");
-                    match next_char {
-                        '-' => {
-                            self.push_token(LAssign, &mut tokens);
-                            self.next();
-                        }
-                        '=' => {
-                            self.push_token(LowerEqual, &mut tokens);
-                            self.next();
-                        }
-                        '<' => {
-                            self.push_token(SuperAssign, &mut tokens);
-                            self.next();
-                            self.next();
-                        }
-                        _ => self.push_token(LowerThan, &mut tokens),
+                self.advance();
+            }
+            '/' => {
+                self.push_token(Divide);
+                self.advance();
+            }
+            '^' => {
+                self.push_token(Power);
+                self.advance();
+            }
+            '+' => {
+                self.push_token(Plus);
+                self.advance();
+            }
+            '?' => {
+                self.push_token(Help);
+                self.advance();
+            }
+            '<' => {
+                match self.lookahead() {
+                    Some('-') => {
+                        self.push_token(LAssign);
+                        self.advance();
                     }
-                    self.next();
-                }
-                '>' => {
-                    let next_char = self.lookahead().expect("Script does not end on '>'");
-                    match next_char {
-                        '=' => {
-                            self.push_token(GreaterEqual, &mut tokens);
-                            self.next();
-                        }
-                        _ => {
-                            self.push_token(GreaterThan, &mut tokens);
-                        }
+                    Some('=') => {
+                        self.push_token(LowerEqual);
+                        self.advance();
                     }
-                    self.next();
-                }
-                '|' => {
-                    let next_char = self.lookahead().expect("Script does not end on '|'");
-                    match next_char {
-                        '|' => {
-                            self.push_token(Or, &mut tokens);
-                            self.next();
-                        }
-                        '>' => {
-                            self.push_token(Pipe, &mut tokens);
-                            self.next();
-                        }
-                        _ => self.push_token(VectorizedOr, &mut tokens),
+                    Some('<') => {
+                        self.push_token(SuperAssign);
+                        self.advance();
+                        self.advance();
                     }
-                    self.next();
+                    _ => self.push_token(LowerThan),
                 }
-                '&' => {
-                    let next_char = self.lookahead().expect("Script does not end on '&'");
-                    match next_char {
-                        '&' => {
-                            self.push_token(And, &mut tokens);
-                            self.next();
-                        }
-                        _ => self.push_token(VectorizedAnd, &mut tokens),
+                self.advance();
+            }
+            '>' => {
+                match self.lookahead() {
+                    Some('=') => {
+                        self.push_token(GreaterEqual);
+                        self.advance();
                     }
-                    self.next();
-                }
-                '=' => {
-                    let next_char = self.lookahead().expect("Script does not end on '='");
-                    match next_char {
-                        '=' => {
-                            self.push_token(Equal, &mut tokens);
-                            self.next();
-                        }
-                        _ => self.push_token(OldAssign, &mut tokens),
+                    _ => {
+                        self.push_token(GreaterThan);
                     }
-                    self.next();
-                }
-                '$' => {
-                    self.push_token(Dollar, &mut tokens);
-                    self.next();
                 }
-                '-' => {
-                    let next_char = self.lookahead().expect("Script does not end on '-'");
-                    match next_char {
-                        '>' => {
-                            self.push_token(RAssign, &mut tokens);
-                            self.next();
-                        }
-                        _ => self.push_token(Minus, &mut tokens),
+                self.advance();
+            }
+            '|' => {
+                match self.lookahead() {
+                    Some('|') => {
+                        self.push_token(Or);
+                        self.advance();
                     }
-                    self.next();
-                }
-                '!' => {
-                    self.next();
-                    match self.current_char {
-                        '=' => {
-                            self.push_token(NotEqual, &mut tokens);
-                            self.next();
-                        }
-                        _ => self.push_token(UnaryNot, &mut tokens),
+                    Some('>') => {
+                        self.push_token(Pipe);
+                        self.advance();
                     }
+                    _ => self.push_token(VectorizedOr),
                 }
-                '.' => {
-                    let next_char = self.lookahead().expect("Script does not end on '.'");
-                    match next_char {
-                        'a'..='z' | 'A'..='Z' => {
-                            self.identifier(&mut tokens);
-                        }
-                        '0'..='9' => {
-                            self.number_literal(&mut tokens);
-                        }
-                        _ => {
-                            trace!(
-                                "Found not alphabetic and non-numeric character after a dot. \
-                                 Treating it as an identifier."
-                            );
-                            self.identifier(&mut tokens);
-                        }
+                self.advance();
+            }
+            '&' => {
+                match self.lookahead() {
+                    Some('&') => {
+                        self.push_token(And);
+                        self.advance();
                     }
+                    _ => self.push_token(VectorizedAnd),
                 }
-                '`' | '_' => {
-                    self.identifier(&mut tokens);
+                self.advance();
+            }
+            '=' => {
+                match self.lookahead() {
+                    Some('=') => {
+                        self.push_token(Equal);
+                        self.advance();
+                    }
+                    _ => self.push_token(OldAssign),
                 }
-                '%' => {
-                    let next_char = self.lookahead().expect("Script does not end on '%'");
-                    match next_char {
-                        '%' => {
-                            self.push_token(Modulo, &mut tokens);
-                            self.next();
-                            self.next();
-                        }
-                        _ => {
-                            let custom_binary_start = self.it;
-                            self.next();
-                            while self.current_char != '%' {
-                                self.next();
-                            }
-                            let custom_binary_end = self.it;
-                            self.push_token(
-                                Special(&self.raw_source[custom_binary_start..=custom_binary_end]),
-                                &mut tokens,
-                            );
-                            self.next()
-                        }
+                self.advance();
+            }
+            '$' => {
+                self.push_token(Dollar);
+                self.advance();
+            }
+            '-' => {
+                match self.lookahead() {
+                    Some('>') => {
+                        self.push_token(RAssign);
+                        self.advance();
                     }
+                    _ => self.push_token(Minus),
                 }
-                'a'..='z' | 'A'..='Z' => match self.lookahead() {
-                    Some('"') | Some('\'') => {
-                        self.raw_string_literal(&mut tokens);
+                self.advance();
+            }
+            '!' => {
+                let start_offset = self.offset;
+                self.advance();
+                match self.current_char {
+                    '=' => {
+                        self.push_token_at(NotEqual, start_offset);
+                        self.advance();
                     }
-                    Some(_) | None => self.identifier_or_reserved(&mut tokens),
-                },
-                '0'..='9' => {
-                    self.number_literal(&mut tokens);
+                    _ => self.push_token_at(UnaryNot, start_offset),
                 }
-                '\\' => {
-                    self.push_token(Lambda, &mut tokens);
-                    self.next();
+            }
+            '.' => match self.lookahead() {
+                Some('a'..='z' | 'A'..='Z') => {
+                    self.identifier();
                 }
-                '#' => {
-                    self.comment(&mut tokens);
+                Some('0'..='9') => {
+                    self.number_literal();
                 }
-                '~' => {
-                    self.push_token(Tilde, &mut tokens);
-                    self.next();
+                _ => {
+                    trace!(
+                        "Found not alphabetic and non-numeric character after a dot. \
+                             Treating it as an identifier."
+                    );
+                    self.identifier();
                 }
-                '@' => {
-                    self.push_token(Slot, &mut tokens);
-                    self.next();
+            },
+            '`' | '_' => {
+                self.identifier();
+            }
+            '%' => match self.lookahead() {
+                Some('%') => {
+                    self.push_token(Modulo);
+                    self.advance();
+                    self.advance();
+                }
+                _ => {
+                    let custom_binary_start = self.it;
+                    self.advance();
+                    while !self.at_end() && self.current_char != '%' {
+                        self.advance();
+                    }
+                    self.push_token_at(
+                        Special(self.source_through_current(custom_binary_start)),
+                        custom_binary_start,
+                    );
+                    self.advance()
+                }
+            },
+            'a'..='z' | 'A'..='Z' => match self.lookahead() {
+                Some('"') | Some('\'') => {
+                    self.raw_string_literal();
                 }
-                ':' => {
-                    self.next();
-                    let next = self.lookahead();
+                Some(_) | None => self.identifier_or_reserved(),
+            },
+            '0'..='9' => {
+                self.number_literal();
+            }
+            '\\' => {
+                self.push_token(Lambda);
+                self.advance();
+            }
+            '#' => {
+                self.comment();
+            }
+            '~' => {
+                self.push_token(Tilde);
+                self.advance();
+            }
+            '@' => {
+                self.push_token(Slot);
+                self.advance();
+            }
+            ':' => {
+                let start_offset = self.offset;
+                self.advance();
+                let next = self.lookahead();
 
-                    match (self.current_char, next) {
-                        // :::
-                        (':', Some(':')) => {
-                            self.push_token(NsGetInt, &mut tokens);
-                            self.next();
-                            self.next();
-                        }
-                        // ::
-                        (':', _) => {
-                            self.push_token(NsGet, &mut tokens);
-                            self.next()
-                        }
-                        // :=
-                        ('=', _) => {
-                            self.push_token(ColonAssign, &mut tokens);
-                            self.next()
-                        }
-                        // :
-                        _ => self.push_token(Colon, &mut tokens),
+                match (self.current_char, next) {
+                    // :::
+                    (':', Some(':')) => {
+                        self.push_token_at(NsGetInt, start_offset);
+                        self.advance();
+                        self.advance();
                     }
-                }
-                c => {
-                    trace!(
-                        "Unexpected character '{c}' around:\n\"\"\"\n{}\n\"\"\"",
-                        &self.raw_source[self.it.saturating_sub(15)
-                            ..self.it.saturating_add(15).min(self.raw_source.len())]
-                    );
-                    self.next();
+                    // ::
+                    (':', _) => {
+                        self.push_token_at(NsGet, start_offset);
+                        self.advance()
+                    }
+                    // :=
+                    ('=', _) => {
+                        self.push_token_at(ColonAssign, start_offset);
+                        self.advance()
+                    }
+                    // :
+                    _ => self.push_token_at(Colon, start_offset),
                 }
             }
+            c => {
+                trace!(
+                    "Unexpected character '{c}' around:\n\"\"\"\n{}\n\"\"\"",
+                    &self.raw_source[self.it.saturating_sub(15)
+                        ..self.it.saturating_add(15).min(self.raw_source.len())]
+                );
+                self.advance();
+            }
         }
-        tokens.push(CommentedToken::new(EOF, self.offset));
-        trace!("Tokenized: {:?}", tokens);
-        Ok(tokens)
+        self.pending.take()
     }
 
-    fn push_token(&mut self, token: Token<'a>, tokens: &mut Vec<CommentedToken<'a>>) {
-        tokens.push(CommentedToken::new(token, self.offset));
+    fn push_token(&mut self, token: Token<'a>) {
+        self.push_token_at(token, self.offset);
     }
 
-    fn raw_string_literal(&mut self, tokens: &mut Vec<CommentedToken<'a>>) {
+    fn push_token_at(&mut self, token: Token<'a>, offset: usize) {
+        self.after_newline = matches!(token, Newline);
+        self.pending = Some(CommentedToken::new(token, offset));
+    }
+
+    fn raw_string_literal(&mut self) {
         let start_offset = self.offset;
         let start_it = self.it;
         // Skip the r in r"..."
-        self.next();
+        self.advance();
 
         // Get the delimiter
         let delimiter = self.current_char;
-        self.next();
+        self.advance();
 
         // Find the inside delimiter
         const START_DELIMS: [char; 3] = ['(', '{', '['];
         const END_DELIMS: [char; 3] = [')', '}', ']'];
         let mut end = vec![];
-        while !START_DELIMS.contains(&self.current_char) {
+        while !self.at_end() && !START_DELIMS.contains(&self.current_char) {
             end.push(self.current_char);
-            self.next();
+            self.advance();
+        }
+        if self.at_end() {
+            self.push_token_at(Literal(&self.raw_source[start_it..]), start_offset);
+            return;
         }
 
         end.push(
@@ -386,72 +399,66 @@ impl<'a> Tokenizer<'a> {
         end.push(delimiter);
 
         // Move past the start delimiter
-        self.next();
+        self.advance();
 
         // Advance until we find the end delimiter
         let mut matching_end_chars = 0;
-        while matching_end_chars < end.len() {
+        while !self.at_end() && matching_end_chars < end.len() {
             if end[matching_end_chars] == self.current_char {
                 matching_end_chars += 1;
             }
-            self.next();
+            self.advance();
         }
 
         // Until self.it because the loop above moves one character past the end delimiter
         // (including ' or ")
-        tokens.push(CommentedToken::new(
-            Literal(&self.raw_source[start_it..self.it]),
-            start_offset,
-        ));
+        self.push_token_at(Literal(&self.raw_source[start_it..self.it]), start_offset);
     }
 
-    fn string_literal(&mut self, tokens: &mut Vec<CommentedToken<'a>>) {
+    fn string_literal(&mut self) {
         let start_offset = self.offset;
         let start_it = self.it;
         let delimiter = self.current_char;
         let mut in_escape = false;
-        self.next();
-        while self.current_char != delimiter || in_escape {
+        self.advance();
+        while !self.at_end() && (self.current_char != delimiter || in_escape) {
             if in_escape {
                 in_escape = !in_escape;
             } else if self.current_char == '\\' {
                 in_escape = true;
             }
-            self.next()
+            self.advance()
         }
-        tokens.push(CommentedToken::new(
-            Literal(&self.raw_source[start_it..=self.it]),
-            start_offset,
-        ));
+        self.push_token_at(Literal(self.source_through_current(start_it)), start_offset);
     }
 
     fn parse_decimal(&mut self) {
         while self.it < self.raw_source.len() && self.current_char.is_ascii_digit() {
-            self.next();
+            self.advance();
         }
     }
 
     fn parse_hexadecimal(&mut self) {
         while self.it < self.raw_source.len() && self.current_char.is_ascii_hexdigit() {
-            self.next();
+            self.advance();
         }
     }
 
-    fn number_literal(&mut self, tokens: &mut Vec<CommentedToken<'a>>) {
+    fn number_literal(&mut self) {
         let start_it = self.it;
         let next = self.lookahead();
         match (self.current_char, next) {
             // Hexadecimal
             // 0x.., 0X..
             ('0', Some(next)) if next == 'x' || next == 'X' => {
-                self.next();
-                self.next();
+                self.advance();
+                self.advance();
                 self.parse_hexadecimal();
                 if self.current_char == '.' {
-                    self.next();
+                    self.advance();
                     self.parse_hexadecimal();
                     if self.current_char == 'p' || self.current_char == 'P' {
-                        self.next();
+                        self.advance();
                         self.parse_hexadecimal();
                     }
                 }
@@ -462,132 +469,129 @@ impl<'a> Tokenizer<'a> {
                 let next = self.lookahead();
                 match (self.current_char, next) {
                     ('.', _) => {
-                        self.next();
+                        self.advance();
                         self.parse_decimal();
                         let next = self.lookahead();
                         match (self.current_char, next) {
                             ('e', Some(next)) if next == '+' || next == '-' => {
-                                self.next();
-                                self.next();
+                                self.advance();
+                                self.advance();
                                 self.parse_decimal();
                             }
                             ('E', Some(next)) if next == '+' || next == '-' => {
-                                self.next();
-                                self.next();
+                                self.advance();
+                                self.advance();
                                 self.parse_decimal();
                             }
                             ('e', _) | ('E', _) => {
-                                self.next();
+                                self.advance();
                                 self.parse_decimal();
                             }
                             ('i', _) => {
-                                self.next();
+                                self.advance();
                             }
                             _ => {}
                         }
                     }
                     ('e', Some(next)) if next == '+' || next == '-' => {
-                        self.next();
-                        self.next();
+                        self.advance();
+                        self.advance();
                         self.parse_decimal();
                     }
                     ('E', Some(next)) if next == '+' || next == '-' => {
-                        self.next();
-                        self.next();
+                        self.advance();
+                        self.advance();
                         self.parse_decimal();
                     }
                     ('e', _) | ('E', _) => {
-                        self.next();
+                        self.advance();
                         self.parse_decimal();
                     }
                     ('L', _) => {
-                        self.next();
+                        self.advance();
                     }
                     ('i', _) => {
-                        self.next();
+                        self.advance();
                     }
                     _ => {}
                 }
             }
         }
-        self.push_token(Literal(&self.raw_source[start_it..self.it]), tokens);
+        self.push_token_at(Literal(&self.raw_source[start_it..self.it]), start_it);
     }
 
-    fn identifier(&mut self, tokens: &mut Vec<CommentedToken<'a>>) {
+    fn identifier(&mut self) {
         let start_it = self.it;
         let mut in_backticks = false;
         let mut escaped = false;
-        while self.it < self.raw_source.len() && in_backticks
-            || self.current_char.is_alphabetic()
-            || self.current_char.is_ascii_digit()
-            || self.current_char == '.'
-            || self.current_char == '_'
-            || self.current_char == '`'
+        while self.it < self.raw_source.len()
+            && (in_backticks
+                || self.current_char.is_alphabetic()
+                || self.current_char.is_ascii_digit()
+                || self.current_char == '.'
+                || self.current_char == '_'
+                || self.current_char == '`')
         {
             match self.current_char {
                 '\\' => {
                     escaped = true;
-                    self.next();
+                    self.advance();
                 }
                 '`' if escaped => {
                     escaped = false;
-                    self.next();
+                    self.advance();
                 }
                 '`' => {
                     in_backticks = !in_backticks;
-                    self.next();
+                    self.advance();
                 }
-                _ => self.next(),
+                _ => self.advance(),
             }
         }
         match &self.raw_source[start_it..self.it] {
-            "TRUE" | "T" => self.push_token(Literal("TRUE"), tokens),
-            "FALSE" | "F" => self.push_token(Literal("FALSE"), tokens),
-            _ => self.push_token(Symbol(&self.raw_source[start_it..self.it]), tokens),
+            "TRUE" | "T" => self.push_token_at(Literal("TRUE"), start_it),
+            "FALSE" | "F" => self.push_token_at(Literal("FALSE"), start_it),
+            _ => self.push_token_at(Symbol(&self.raw_source[start_it..self.it]), start_it),
         }
     }
 
-    fn identifier_or_reserved(&mut self, tokens: &mut Vec<CommentedToken<'a>>) {
+    fn identifier_or_reserved(&mut self) {
         let start_it = self.it;
         while self.it < self.raw_source.len() && !SYMBOL_ENDING.contains(&self.current_char) {
-            self.next();
+            self.advance();
         }
 
         match &self.raw_source[start_it..self.it] {
-            "continue" => self.push_token(Continue, tokens),
-            "break" => self.push_token(Break, tokens),
-            "for" => self.push_token(For, tokens),
-            "if" => self.push_token(If, tokens),
-            "else" => self.push_token(Else, tokens),
-            "in" => self.push_token(In, tokens),
-            "while" => self.push_token(While, tokens),
-            "repeat" => self.push_token(Repeat, tokens),
-            "function" => self.push_token(Function, tokens),
-            "TRUE" | "T" => self.push_token(Literal("TRUE"), tokens),
-            "FALSE" | "F" => self.push_token(Literal("FALSE"), tokens),
-            _ => self.push_token(Symbol(&self.raw_source[start_it..self.it]), tokens),
+            "continue" => self.push_token_at(Continue, start_it),
+            "break" => self.push_token_at(Break, start_it),
+            "for" => self.push_token_at(For, start_it),
+            "if" => self.push_token_at(If, start_it),
+            "else" => self.push_token_at(Else, start_it),
+            "in" => self.push_token_at(In, start_it),
+            "while" => self.push_token_at(While, start_it),
+            "repeat" => self.push_token_at(Repeat, start_it),
+            "function" => self.push_token_at(Function, start_it),
+            "TRUE" | "T" => self.push_token_at(Literal("TRUE"), start_it),
+            "FALSE" | "F" => self.push_token_at(Literal("FALSE"), start_it),
+            _ => self.push_token_at(Symbol(&self.raw_source[start_it..self.it]), start_it),
         }
     }
 
-    fn comment(&mut self, tokens: &mut Vec<CommentedToken<'a>>) {
+    fn comment(&mut self) {
         let start_it = self.it;
         while self.it < self.raw_source.len() && self.current_char != '\n' {
-            self.next();
+            self.advance();
         }
 
-        match tokens.last() {
-            Some(CommentedToken {
-                token: Newline,
-                offset: _,
-                leading_comments: _,
-                inline_comment: _,
-            }) => self.push_token(Comment(&self.raw_source[start_it..self.it]), tokens),
-            Some(_) => self.push_token(InlineComment(&self.raw_source[start_it..self.it]), tokens),
-            None => self.push_token(Comment(&self.raw_source[start_it..self.it]), tokens),
+        let comment = &self.raw_source[start_it..self.it];
+        if self.after_newline {
+            self.push_token_at(Comment(comment), start_it);
+        } else {
+            self.push_token_at(InlineComment(comment), start_it);
         }
     }
 
-    fn next(&mut self) {
+    fn advance(&mut self) {
         if let Some((new_offset, new_char)) = self.source.next() {
             self.offset = new_offset;
             self.it = new_offset;
@@ -598,11 +602,40 @@ impl<'a> Tokenizer<'a> {
         }
     }
 
+    fn at_end(&self) -> bool {
+        self.it >= self.raw_source.len()
+    }
+
+    /// Returns the source from `start` up to and including the current character.
+    ///
+    /// Unterminated strings and operators run until the end of the source.
+    fn source_through_current(&self, start: usize) -> &'a str {
+        if self.at_end() {
+            &self.raw_source[start..]
+        } else {
+            &self.raw_source[start..self.it + self.current_char.len_utf8()]
+        }
+    }
+
     fn lookahead(&self) -> Option<char> {
-        self.source
-            .clone()
-            .peekable()
-            .next()
-            .map(|(_, new_char)| new_char)
+        self.source.as_str().chars().next()
+    }
+}
+
+impl<'a> Iterator for Tokenizer<'a> {
+    type Item = Result<CommentedToken<'a>, crate::Error>;
+
+    /// Returns the next token. The last token is always [`EOF`].
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+        while !self.at_end() {
+            if let Some(token) = self.next_token() {
+                return Some(Ok(token));
+            }
+        }
+        self.finished = true;
+        Some(Ok(CommentedToken::new(EOF, self.offset)))
     }
 }
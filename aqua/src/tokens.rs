@@ -7,7 +7,7 @@
 pub struct CommentedToken<'a> {
     /// The actual token stored in this struct.
     pub token: Token<'a>,
-    /// The column offset of the start of this token.
+    /// The byte offset of the start of this token in the source.
     pub offset: usize,
     /// Preceding comments.
     pub leading_comments: Option<Vec<&'a str>>,
@@ -0,0 +1,73 @@
+use tergo_tokenizer::{
+    incremental::{TextEdit, relex},
+    tokenizer::Tokenizer,
+    tokens::{CommentedToken, Token},
+};
+
+fn positioned<'a>(tokens: &[CommentedToken<'a>]) -> Vec<(Token<'a>, usize)> {
+    tokens
+        .iter()
+        .map(|token| (token.token.clone(), token.offset))
+        .collect()
+}
+
+fn assert_relex_matches_full_tokenize(source: &str, edit: TextEdit) {
+    let old_tokens = Tokenizer::new(source).tokenize().unwrap();
+    let new_source = edit.apply(source);
+    let relexed = relex(&old_tokens, &edit, &new_source).unwrap();
+    let expected = Tokenizer::new(&new_source).tokenize().unwrap();
+    assert_eq!(
+        positioned(&relexed),
+        positioned(&expected),
+        "Relexing {source:?} with {edit:?}"
+    );
+}
+
+#[test]
+fn relex_matches_full_tokenize() {
+    let source = "a <- 1\n# comment\nf <- function(x) {\n  x + 1 # inline\n}\nb <- T\n";
+    let examples = [
+        TextEdit::new(5..6, "10"),
+        TextEdit::new(0..0, "x <- 2\n"),
+        TextEdit::new(source.len()..source.len(), "c <- 3"),
+        TextEdit::new(7..17, ""),
+        TextEdit::new(30..30, "\n  y <- x"),
+        TextEdit::new(40..41, "*"),
+        TextEdit::new(0..source.len(), "new"),
+        TextEdit::new(source.len() - 2..source.len() - 1, "FALSE"),
+    ];
+    for edit in examples {
+        assert_relex_matches_full_tokenize(source, edit);
+    }
+}
+
+#[test]
+fn relex_handles_strings_spanning_lines() {
+    let source = "a <- 'one\ntwo'\nb <- 2\nc <- 3\n";
+    let examples = [
+        // Removes the opening quote
+        TextEdit::new(5..6, ""),
+        // Opens a string that runs over the following lines
+        TextEdit::new(16..16, "\""),
+        // Opens a string that is never closed
+        TextEdit::new(source.len()..source.len(), "d <- \""),
+    ];
+    for edit in examples {
+        assert_relex_matches_full_tokenize(source, edit);
+    }
+}
+
+#[test]
+fn unterminated_constructs_end_at_the_end_of_the_source() {
+    let examples = [
+        ("'abc", Token::Literal("'abc")),
+        ("r\"(abc", Token::Literal("r\"(abc")),
+        ("r\"abc", Token::Literal("r\"abc")),
+        ("%in", Token::Special("%in")),
+    ];
+    for (source, expected) in examples {
+        let tokens = Tokenizer::new(source).tokenize().unwrap();
+        assert_eq!(tokens[0].token, expected);
+        assert_eq!(tokens[1].token, Token::EOF);
+    }
+}
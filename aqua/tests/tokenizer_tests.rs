@@ -352,3 +352,106 @@ fn escape_in_strings() {
         assert!(!res.is_empty())
     }
 }
+
+#[test]
+fn offsets_point_at_the_start_of_tokens() {
+    let example = "abc <- !x %in% 1:2 # comment\n12 != T";
+    let mut tokenizer = Tokenizer::new(example);
+    let tokens = tokenizer.tokenize().unwrap();
+    let offsets = tokens
+        .into_iter()
+        .map(|token| (token.token, token.offset))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        offsets,
+        vec![
+            (Token::Symbol("abc"), 0),
+            (Token::LAssign, 4),
+            (Token::UnaryNot, 7),
+            (Token::Symbol("x"), 8),
+            (Token::Special("%in%"), 10),
+            (Token::Literal("1"), 15),
+            (Token::Colon, 16),
+            (Token::Literal("2"), 17),
+            (Token::InlineComment("# comment"), 19),
+            (Token::Newline, 28),
+            (Token::Literal("12"), 29),
+            (Token::NotEqual, 32),
+            (Token::Literal("TRUE"), 35),
+            (Token::EOF, 36),
+        ]
+    );
+}
+
+#[test]
+fn accessors_end_symbols() {
+    let examples = [
+        (
+            "pkg::fun",
+            vec![
+                Token::Symbol("pkg"),
+                Token::NsGet,
+                Token::Symbol("fun"),
+                Token::EOF,
+            ],
+        ),
+        (
+            "obj@slot",
+            vec![
+                Token::Symbol("obj"),
+                Token::Slot,
+                Token::Symbol("slot"),
+                Token::EOF,
+            ],
+        ),
+        (
+            "y~x",
+            vec![
+                Token::Symbol("y"),
+                Token::Tilde,
+                Token::Symbol("x"),
+                Token::EOF,
+            ],
+        ),
+    ];
+    for (example, expected) in examples {
+        let mut tokenizer = Tokenizer::new(example);
+        let tokens = tokenizer.tokenize().unwrap();
+        let tokens = tokens
+            .into_iter()
+            .map(|token| token.token)
+            .collect::<Vec<_>>();
+        assert_eq!(tokens, expected);
+    }
+}
+
+#[test]
+fn iterates_over_tokens() {
+    let example = "# comment\nf <- function(x) x ** 2 # inline\nf(1)";
+    let iterated = Tokenizer::new(example)
+        .map(|token| token.unwrap())
+        .map(|token| (token.token, token.offset))
+        .collect::<Vec<_>>();
+    let tokenized = Tokenizer::new(example)
+        .tokenize()
+        .unwrap()
+        .into_iter()
+        .map(|token| (token.token, token.offset))
+        .collect::<Vec<_>>();
+    assert_eq!(iterated, tokenized);
+    assert_eq!(iterated[0].0, Token::Comment("# comment"));
+    assert_eq!(iterated.last().unwrap().0, Token::EOF);
+
+    let mut tokenizer = Tokenizer::new("a");
+    assert_eq!(tokenizer.next().unwrap().unwrap().token, Token::Symbol("a"));
+    assert_eq!(tokenizer.next().unwrap().unwrap().token, Token::EOF);
+    assert!(tokenizer.next().is_none());
+}
+
+#[test]
+fn operators_at_the_end_of_the_source() {
+    for example in ["a *", "a <", "a >", "a |", "a &", "a =", "a -", "a %", "a ."] {
+        let tokens = Tokenizer::new(example).tokenize().unwrap();
+        assert_eq!(tokens.last().unwrap().token, Token::EOF);
+    }
+}
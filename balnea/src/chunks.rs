@@ -0,0 +1,259 @@
+use log::{debug, warn};
+
+use crate::{Config, format_lf};
+
+/// Formats the R chunks of an R Markdown or a Quarto document.
+///
+/// The chunks are the fenced code blocks whose header starts with `{r`,
+/// such as ```` ```{r setup, echo=FALSE} ````. See [format_chunks]
+/// for what is formatted and what is kept as it is.
+pub fn tergo_format_markdown(input: &str, config: Option<&Config>) -> String {
+    format_chunks(input, config, ChunkSyntax::Markdown)
+}
+
+/// Formats the R chunks of a Sweave or knitr LaTeX document (`.Rnw`).
+///
+/// The chunks start with a `<<label, options>>=` line and end
+/// with a line starting with `@` or with the start of the next chunk.
+/// See [format_chunks] for what is formatted and what is kept as it is.
+pub fn tergo_format_noweb(input: &str, config: Option<&Config>) -> String {
+    format_chunks(input, config, ChunkSyntax::Noweb)
+}
+
+/// Formats the R chunks of a knitr HTML document (`.Rhtml`).
+///
+/// The chunks start with a `<!--begin.rcode label, options` line
+/// and end with an `end.rcode-->` line. See [format_chunks]
+/// for what is formatted and what is kept as it is.
+pub fn tergo_format_rhtml(input: &str, config: Option<&Config>) -> String {
+    format_chunks(input, config, ChunkSyntax::Rhtml)
+}
+
+/// The delimiters of the R chunks in a type of document.
+#[derive(Debug, Clone, Copy)]
+enum ChunkSyntax {
+    Markdown,
+    Noweb,
+    Rhtml,
+}
+
+/// Formats the bodies of the R chunks of a document.
+///
+/// The chunk options written as `#|` comments at the top of a chunk
+/// are kept as they are. Everything outside of the bodies of the chunks
+/// is kept byte-for-byte, including the other fenced blocks of Markdown,
+/// which may show the syntax of the chunks. The formatted bodies keep
+/// the line endings of the document.
+///
+/// The chunks which cannot be formatted are left as they are.
+/// They are reported, unless they are not evaluated (`eval=FALSE`),
+/// because such chunks often hold code that is not meant to parse.
+fn format_chunks(input: &str, config: Option<&Config>, syntax: ChunkSyntax) -> String {
+    let default_config = Config::default();
+    let config = config.unwrap_or(&default_config);
+    let carriage_return = if input.contains("\r\n") { "\r" } else { "" };
+    let lines: Vec<&str> = input.split('\n').collect();
+    let mut formatted: Vec<String> = Vec::with_capacity(lines.len());
+    let mut index = 0;
+    while index < lines.len() {
+        let line = lines[index];
+        index += 1;
+        formatted.push(line.to_string());
+        let Some(opening) = ChunkOpening::parse(line, syntax) else {
+            if let (ChunkSyntax::Markdown, Some(fence)) = (syntax, Fence::parse(line)) {
+                while index < lines.len() {
+                    formatted.push(lines[index].to_string());
+                    index += 1;
+                    if fence.is_closed_by(lines[index - 1]) {
+                        break;
+                    }
+                }
+            }
+            continue;
+        };
+        let body_start = index;
+        while index < lines.len() && !opening.is_closed_by(lines[index]) {
+            index += 1;
+        }
+        if index == lines.len() && !matches!(syntax, ChunkSyntax::Noweb) {
+            debug!("The chunk at line {body_start} is not closed");
+            formatted.extend(lines[body_start..].iter().map(|line| line.to_string()));
+            break;
+        }
+        formatted.extend(opening.format_body(
+            &lines[body_start..index],
+            body_start + 1,
+            config,
+            carriage_return,
+        ));
+        if let ChunkSyntax::Markdown = syntax {
+            // The closing fence does not open a fenced block
+            formatted.push(lines[index].to_string());
+            index += 1;
+        }
+    }
+    formatted.join("\n")
+}
+
+/// The opening line of a fenced block of Markdown.
+struct Fence {
+    /// The backtick or the tilde
+    marker: char,
+    length: usize,
+}
+
+impl Fence {
+    fn parse(line: &str) -> Option<Self> {
+        let trimmed = line.trim_start();
+        let marker = trimmed.chars().next().filter(|c| matches!(c, '`' | '~'))?;
+        let length = trimmed.chars().take_while(|&c| c == marker).count();
+        (length >= 3).then_some(Self { marker, length })
+    }
+
+    fn is_closed_by(&self, line: &str) -> bool {
+        let trimmed = line.trim();
+        trimmed.chars().count() >= self.length && trimmed.chars().all(|c| c == self.marker)
+    }
+}
+
+/// The opening line of an R chunk.
+struct ChunkOpening<'a> {
+    syntax: ChunkSyntax,
+    indent: &'a str,
+    /// The number of backticks of a Markdown fence
+    backticks: usize,
+    /// The chunk options written in the opening line
+    options: &'a str,
+}
+
+impl<'a> ChunkOpening<'a> {
+    /// Returns the opening, if the line opens an R chunk.
+    fn parse(line: &'a str, syntax: ChunkSyntax) -> Option<Self> {
+        let trimmed = line.trim_start();
+        let indent = &line[..line.len() - trimmed.len()];
+        let (backticks, options) = match syntax {
+            ChunkSyntax::Markdown => {
+                let backticks = trimmed.chars().take_while(|&c| c == '`').count();
+                if backticks < 3 {
+                    return None;
+                }
+                let header = trimmed[backticks..].trim().strip_prefix('{')?;
+                let options = header.strip_prefix(['r', 'R'])?;
+                if !options.starts_with([' ', ',', '}']) {
+                    return None;
+                }
+                (backticks, options.trim_end().trim_end_matches('}'))
+            }
+            ChunkSyntax::Noweb => {
+                let options = trimmed.trim_end().strip_prefix("<<")?.strip_suffix(">>=")?;
+                (0, options)
+            }
+            ChunkSyntax::Rhtml => (0, trimmed.trim_end().strip_prefix("<!--begin.rcode")?),
+        };
+        Some(Self {
+            syntax,
+            indent,
+            backticks,
+            options,
+        })
+    }
+
+    fn is_closed_by(&self, line: &str) -> bool {
+        let trimmed = line.trim();
+        match self.syntax {
+            ChunkSyntax::Markdown => {
+                trimmed.len() >= self.backticks
+                    && trimmed.chars().all(|c| c == '`')
+                    && line.starts_with(self.indent)
+            }
+            ChunkSyntax::Noweb => {
+                trimmed == "@"
+                    || trimmed.starts_with("@ ")
+                    || ChunkOpening::parse(line, self.syntax).is_some()
+            }
+            ChunkSyntax::Rhtml => trimmed == "end.rcode-->",
+        }
+    }
+
+    /// Returns whether the chunk is evaluated, according to its options.
+    fn is_evaluated(&self, comment_options: &[&str]) -> bool {
+        let is_header_off = self.options.split(',').any(|option| {
+            option.split_once('=').is_some_and(|(key, value)| {
+                key.trim() == "eval" && matches!(value.trim(), "FALSE" | "F")
+            })
+        });
+        let is_comment_off = comment_options.iter().any(|option| {
+            option.trim_start()["#|".len()..]
+                .split_once(':')
+                .is_some_and(|(key, value)| key.trim() == "eval" && value.trim() == "false")
+        });
+        !is_header_off && !is_comment_off
+    }
+
+    /// Formats the body of the chunk.
+    ///
+    /// `line_number` is the number of the first line of the body.
+    /// The formatted lines end with `carriage_return`, as the lines
+    /// of the document do.
+    fn format_body(
+        &self,
+        body: &[&str],
+        line_number: usize,
+        config: &Config,
+        carriage_return: &str,
+    ) -> Vec<String> {
+        let unchanged = || body.iter().map(|line| line.to_string()).collect();
+        let options_count = body
+            .iter()
+            .take_while(|line| line.trim_start().starts_with("#|"))
+            .count();
+        let (options, code) = body.split_at(options_count);
+        let leading_blank_lines = code
+            .iter()
+            .take_while(|line| line.trim().is_empty())
+            .count();
+        let trailing_blank_lines = code[leading_blank_lines..]
+            .iter()
+            .rev()
+            .take_while(|line| line.trim().is_empty())
+            .count();
+        let code = &code[leading_blank_lines..code.len() - trailing_blank_lines];
+        if code.is_empty() || !code.iter().all(|line| line.starts_with(self.indent)) {
+            return unchanged();
+        }
+        let source = code
+            .iter()
+            .map(|line| line[self.indent.len()..].trim_end_matches('\r'))
+            .collect::<Vec<_>>()
+            .join("\n");
+        match format_lf(&source, config) {
+            Ok(formatted) => {
+                let formatted = formatted.trim_end().split('\n').map(|line| {
+                    if line.is_empty() {
+                        carriage_return.to_string()
+                    } else {
+                        format!("{}{line}{carriage_return}", self.indent)
+                    }
+                });
+                body[..options_count + leading_blank_lines]
+                    .iter()
+                    .map(|line| line.to_string())
+                    .chain(formatted)
+                    .chain(
+                        body[body.len() - trailing_blank_lines..]
+                            .iter()
+                            .map(|line| line.to_string()),
+                    )
+                    .collect()
+            }
+            Err(error) if self.is_evaluated(options) => {
+                warn!("Could not format the chunk at line {line_number}: {error}");
+                unchanged()
+            }
+            Err(error) => {
+                debug!("Could not format the unevaluated chunk at line {line_number}: {error}");
+                unchanged()
+            }
+        }
+    }
+}
@@ -1,11 +1,29 @@
+mod chunks;
+mod position;
+mod rd;
+mod roxygen;
+
+pub use chunks::{tergo_format_markdown, tergo_format_noweb, tergo_format_rhtml};
+pub use formatter::config::AssignmentOperator;
+pub use formatter::config::BraceStyle;
+pub use formatter::config::CallArgumentsLayout;
 pub use formatter::config::Config;
+pub use formatter::config::EndOfLine;
+pub use formatter::config::EnforceBraces;
 pub use formatter::config::FunctionLineBreaks;
+pub use formatter::config::QuoteStyle;
+pub use formatter::config::SemicolonStatements;
+pub use formatter::config::Style;
 use formatter::format_code;
 use log::trace;
+pub use parser::ParseError;
 use parser::{
     ast::{Expression, TermExpr},
     parse, pre_parse,
 };
+pub use position::{Position, offset_to_position, position_to_offset, tergo_syntax_error_range};
+pub use rd::tergo_format_rd;
+use std::ops::Range;
 use tokenizer::Tokenizer;
 
 /// Format the input code with the given configuration.
@@ -35,6 +53,18 @@ use tokenizer::Tokenizer;
 pub fn tergo_format(input: &str, config: Option<&Config>) -> Result<String, String> {
     let default_config = Config::default();
     let config = config.unwrap_or(&default_config);
+    let formatted = format_lf(input, config)?;
+    let formatted = if config.insert_final_newline.0 {
+        formatted
+    } else {
+        formatted.trim_end_matches('\n').to_string()
+    };
+    Ok(with_line_endings(formatted, config))
+}
+
+/// Formats the code with `\n` line endings and a final new line,
+/// whatever the line ending options of the configuration.
+pub(crate) fn format_lf(input: &str, config: &Config) -> Result<String, String> {
     trace!("Formatting with config: {config}");
     let mut tokenizer = Tokenizer::new(input);
     trace!("Tokenizer created");
@@ -43,7 +73,7 @@ pub fn tergo_format(input: &str, config: Option<&Config>) -> Result<String, Stri
     let tokens_without_comments = pre_parse(&mut commented_tokens);
     let tokens_without_comments = parser::Input(&tokens_without_comments);
     trace!("Tokens without comments: {}", &tokens_without_comments);
-    let cst = parse(tokens_without_comments).map_err(|e| {
+    let cst = parse(tokens_without_comments).map_err(|ParseError { message: e, .. }| {
         format!(
             "Parsing error: {}{}",
             &e[..120.min(e.len())],
@@ -52,5 +82,128 @@ pub fn tergo_format(input: &str, config: Option<&Config>) -> Result<String, Stri
     })?;
     let top_node = Expression::Term(Box::new(TermExpr::new(None, cst, None)));
     trace!("CST: {:?}", top_node);
-    Ok(format_code(top_node, config))
+    let formatted = format_code(top_node, config);
+    if config.format_roxygen.0 {
+        Ok(roxygen::format_roxygen(&formatted, config))
+    } else {
+        Ok(formatted)
+    }
+}
+
+/// Returns the first syntax error of the code, if it does not parse.
+///
+/// The offset of the error is the byte offset of the token
+/// which could not be parsed.
+///
+/// # Example
+///
+/// ```rust
+/// use tergo_lib::tergo_syntax_error;
+///
+/// assert!(tergo_syntax_error("f(x)").is_none());
+/// assert_eq!(tergo_syntax_error("f(x))").unwrap().offset, 4);
+/// ```
+pub fn tergo_syntax_error(input: &str) -> Option<ParseError> {
+    let mut commented_tokens = match Tokenizer::new(input).tokenize() {
+        Ok(tokens) => tokens,
+        Err(e) => {
+            return Some(ParseError {
+                offset: 0,
+                message: format!("{e}"),
+            });
+        }
+    };
+    let tokens = pre_parse(&mut commented_tokens);
+    parse(parser::Input(&tokens)).err()
+}
+
+/// Formats the top-level statements of the code overlapping a range.
+///
+/// The range is a range of byte offsets in the input. It is extended
+/// to the whole lines of the top-level statements it overlaps, and only
+/// these statements are formatted. An empty range selects the statement
+/// containing its start.
+///
+/// # Returns
+///
+/// The byte range of the input to replace and its replacement.
+/// The range is empty if no statement overlaps the given range.
+///
+/// # Example
+///
+/// ```rust
+/// use tergo_lib::tergo_format_range;
+///
+/// let input = "a<-1\nb<-2\n";
+/// let (range, formatted) = tergo_format_range(input, 6..7, None).unwrap();
+///
+/// assert_eq!(range, 5..9);
+/// assert_eq!(formatted, "b <- 2");
+/// ```
+pub fn tergo_format_range(
+    input: &str,
+    range: Range<usize>,
+    config: Option<&Config>,
+) -> Result<(Range<usize>, String), String> {
+    let mut commented_tokens = Tokenizer::new(input)
+        .tokenize()
+        .map_err(|e| format!("{e}"))?;
+    let tokens = pre_parse(&mut commented_tokens);
+    let expressions = parse(parser::Input(&tokens))?;
+    let line_start = |offset: usize| input[..offset].rfind('\n').map_or(0, |index| index + 1);
+    let mut statement_starts: Vec<usize> = vec![];
+    for expression in &expressions {
+        if matches!(
+            expression,
+            Expression::Whitespace(_) | Expression::Semicolon(..) | Expression::EOF(_)
+        ) {
+            continue;
+        }
+        let mut first_offset = None;
+        expression.for_each_token(&mut |token| {
+            first_offset.get_or_insert(token.offset);
+        });
+        if let Some(start) = first_offset.map(line_start) {
+            if statement_starts.last() != Some(&start) {
+                statement_starts.push(start);
+            }
+        }
+    }
+    let statements = statement_starts.iter().enumerate().map(|(index, &start)| {
+        start
+            ..statement_starts
+                .get(index + 1)
+                .copied()
+                .unwrap_or(input.len())
+    });
+    let overlaps = |statement: &Range<usize>| {
+        if range.is_empty() {
+            statement.contains(&range.start)
+                || (statement.end == input.len() && range.start == input.len())
+        } else {
+            statement.start < range.end && range.start < statement.end
+        }
+    };
+    let mut selected = statements.filter(overlaps);
+    let Some(first) = selected.next() else {
+        return Ok((range.start..range.start, String::new()));
+    };
+    let end = selected.next_back().map_or(first.end, |last| last.end);
+    let code = input[first.start..end].trim_end();
+    let default_config = Config::default();
+    let config = config.unwrap_or(&default_config);
+    let formatted = format_lf(code, config)?;
+    Ok((
+        first.start..first.start + code.len(),
+        with_line_endings(formatted.trim_end().to_string(), config),
+    ))
+}
+
+/// Replaces the `\n` line endings of the formatted code
+/// with the line ending of the configuration.
+fn with_line_endings(formatted: String, config: &Config) -> String {
+    match config.end_of_line {
+        EndOfLine::Lf => formatted,
+        end_of_line => formatted.replace('\n', end_of_line.as_str()),
+    }
 }
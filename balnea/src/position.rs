@@ -0,0 +1,88 @@
+use std::ops::Range;
+
+use crate::tergo_syntax_error;
+
+/// A position in the code, as in the Language Server Protocol.
+///
+/// The line is zero-based and the character counts the UTF-16 code units
+/// from the start of the line.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Position {
+    pub line: u32,
+    pub character: u32,
+}
+
+/// Converts a byte offset of the code into a position.
+///
+/// # Example
+///
+/// ```rust
+/// use tergo_lib::{Position, offset_to_position};
+///
+/// let position = offset_to_position("a <- 1\nb <- \"é\"\n", 12);
+///
+/// assert_eq!(position, Position { line: 1, character: 5 });
+/// ```
+pub fn offset_to_position(code: &str, offset: usize) -> Position {
+    let before = &code[..offset];
+    let line_start = before.rfind('\n').map_or(0, |index| index + 1);
+    Position {
+        line: before.matches('\n').count() as u32,
+        character: before[line_start..].encode_utf16().count() as u32,
+    }
+}
+
+/// Converts a position into a byte offset of the code.
+///
+/// The positions past the end of a line or of the code are clamped.
+///
+/// # Example
+///
+/// ```rust
+/// use tergo_lib::{Position, position_to_offset};
+///
+/// let code = "a <- 1\nb <- 2\n";
+///
+/// assert_eq!(position_to_offset(code, Position { line: 1, character: 2 }), 9);
+/// assert_eq!(position_to_offset(code, Position { line: 1, character: 80 }), 13);
+/// ```
+pub fn position_to_offset(code: &str, position: Position) -> usize {
+    let mut line_start = 0;
+    for _ in 0..position.line {
+        match code[line_start..].find('\n') {
+            Some(index) => line_start += index + 1,
+            None => return code.len(),
+        }
+    }
+    let line = code[line_start..].split('\n').next().unwrap_or_default();
+    let mut utf16_character = 0;
+    for (index, c) in line.char_indices() {
+        if utf16_character >= position.character as usize {
+            return line_start + index;
+        }
+        utf16_character += c.len_utf16();
+    }
+    line_start + line.len()
+}
+
+/// Returns the byte range of the first syntax error of the code,
+/// if it does not parse.
+///
+/// The range covers the text up to the next whitespace from the token
+/// which could not be parsed. It is empty if the error is at the end
+/// of the code.
+///
+/// # Example
+///
+/// ```rust
+/// use tergo_lib::tergo_syntax_error_range;
+///
+/// assert_eq!(tergo_syntax_error_range("f(x))\n"), Some(4..5));
+/// assert_eq!(tergo_syntax_error_range("f(x)\n"), None);
+/// ```
+pub fn tergo_syntax_error_range(code: &str) -> Option<Range<usize>> {
+    let error = tergo_syntax_error(code)?;
+    let offset = error.offset.min(code.len());
+    let token = code[offset..].split_whitespace().next().unwrap_or_default();
+    Some(offset..offset + token.len())
+}
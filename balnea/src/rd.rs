@@ -0,0 +1,291 @@
+use log::{debug, warn};
+
+use crate::{Config, format_lf};
+
+/// The sections of an Rd file holding R code.
+const CODE_SECTIONS: [&str; 2] = ["examples", "usage"];
+
+/// The macros of the examples which wrap R code.
+const WRAPPER_MACROS: [&str; 4] = ["dontrun", "donttest", "dontshow", "testonly"];
+
+/// Formats the R code of the `\examples{}` and `\usage{}` sections
+/// of an Rd file.
+///
+/// The code is unescaped before formatting and escaped again afterwards,
+/// so the `%`, `{`, `}` and `\` keep their Rd meaning. The code wrapped
+/// in `\dontrun{}`, `\donttest{}`, `\dontshow{}` and `\testonly{}` is
+/// formatted too. The sections with other macros, such as `\method{}{}`,
+/// or with Rd comments are left as they are, and so are the sections
+/// which cannot be formatted. Everything outside of the sections
+/// is kept byte-for-byte.
+pub fn tergo_format_rd(input: &str, config: Option<&Config>) -> String {
+    let default_config = Config::default();
+    let config = config.unwrap_or(&default_config);
+    let mut formatted = String::with_capacity(input.len());
+    let mut position = 0;
+    while let Some((section, body_start)) = next_code_section(input, position) {
+        let mut parser = RdParser {
+            source: input,
+            position: body_start,
+        };
+        let line_number = input[..body_start].matches('\n').count() + 1;
+        let section_end = match parser.parse_block() {
+            Ok(nodes) => {
+                let code = render(&nodes, config).map(|lines| lines.join("\n"));
+                match code {
+                    Ok(code) => {
+                        formatted.push_str(&input[position..body_start]);
+                        formatted.push('\n');
+                        if !code.is_empty() {
+                            formatted.push_str(&code);
+                            formatted.push('\n');
+                        }
+                        formatted.push('}');
+                        position = parser.position;
+                        continue;
+                    }
+                    Err(error) => {
+                        warn!(
+                            "Could not format the \\{section} section at line {line_number}: {error}"
+                        );
+                        parser.position
+                    }
+                }
+            }
+            Err(error) => {
+                debug!("Skipping the \\{section} section at line {line_number}: {error}");
+                body_start
+            }
+        };
+        formatted.push_str(&input[position..section_end]);
+        position = section_end;
+    }
+    formatted.push_str(&input[position..]);
+    formatted
+}
+
+/// Returns the name of the next code section starting at a line
+/// and the position of its body.
+fn next_code_section(input: &str, from: usize) -> Option<(&'static str, usize)> {
+    let mut line_start = from;
+    loop {
+        let line = &input[line_start..];
+        let trimmed = line.trim_start_matches([' ', '\t']);
+        let section = CODE_SECTIONS.into_iter().find(|section| {
+            trimmed
+                .strip_prefix('\\')
+                .and_then(|rest| rest.strip_prefix(section))
+                .is_some_and(|rest| rest.starts_with('{'))
+        });
+        if let Some(section) = section {
+            let body_start = input.len() - trimmed.len() + "\\{".len() + section.len();
+            return Some((section, body_start));
+        }
+        line_start += line.find('\n')? + 1;
+    }
+}
+
+/// A piece of the R-like text of an Rd section.
+#[derive(Debug)]
+enum RdNode<'a> {
+    /// The unescaped R code.
+    Code(String),
+    /// A macro with its arguments, such as `\dontrun{code}`.
+    Macro(&'a str, Vec<Vec<RdNode<'a>>>),
+}
+
+/// Parses the R-like text of the Rd sections.
+struct RdParser<'a> {
+    source: &'a str,
+    position: usize,
+}
+
+impl<'a> RdParser<'a> {
+    fn next_char(&mut self) -> Option<char> {
+        let c = self.source[self.position..].chars().next()?;
+        self.position += c.len_utf8();
+        Some(c)
+    }
+
+    fn peek_char(&self) -> Option<char> {
+        self.source[self.position..].chars().next()
+    }
+
+    /// Parses the text up to and including the closing brace
+    /// of the current block.
+    fn parse_block(&mut self) -> Result<Vec<RdNode<'a>>, String> {
+        let mut nodes = vec![];
+        let mut code = String::new();
+        let mut depth = 0;
+        let mut quote = None;
+        // Whether the last character of the code is a backslash
+        // escaping the next character of a string
+        let mut is_escaping = false;
+        let mut is_comment = false;
+        loop {
+            let Some(c) = self.next_char() else {
+                return Err("the block is not closed".to_string());
+            };
+            match c {
+                '%' => return Err("Rd comments are not supported".to_string()),
+                '\\' => match self.next_char() {
+                    Some(escaped @ ('\\' | '%' | '{' | '}')) => {
+                        code.push(escaped);
+                        is_escaping = quote.is_some() && escaped == '\\' && !is_escaping;
+                    }
+                    Some(c) if quote.is_some() => {
+                        // A lone backslash in a string is an R escape, such as \n or \'
+                        code.push('\\');
+                        if is_escaping && quote == Some(c) {
+                            quote = None;
+                        }
+                        code.push(c);
+                        is_escaping = false;
+                    }
+                    Some(c) if c.is_ascii_alphabetic() && !is_comment => {
+                        let name_start = self.position - 1;
+                        while self.peek_char().is_some_and(|c| c.is_ascii_alphanumeric()) {
+                            self.next_char();
+                        }
+                        let name = &self.source[name_start..self.position];
+                        let mut arguments = vec![];
+                        while self.peek_char() == Some('{') {
+                            self.next_char();
+                            arguments.push(self.parse_block()?);
+                        }
+                        if !code.is_empty() {
+                            nodes.push(RdNode::Code(std::mem::take(&mut code)));
+                        }
+                        nodes.push(RdNode::Macro(name, arguments));
+                    }
+                    _ => return Err("unexpected backslash".to_string()),
+                },
+                c if is_escaping => {
+                    is_escaping = false;
+                    code.push(c);
+                }
+                '\n' => {
+                    is_comment = false;
+                    code.push(c);
+                }
+                c if quote == Some(c) => {
+                    quote = None;
+                    code.push(c);
+                }
+                '"' | '\'' | '`' if quote.is_none() && !is_comment => {
+                    quote = Some(c);
+                    code.push(c);
+                }
+                '#' if quote.is_none() => {
+                    is_comment = true;
+                    code.push(c);
+                }
+                '{' if quote.is_none() => {
+                    depth += 1;
+                    code.push(c);
+                }
+                '}' if quote.is_none() && depth == 0 => break,
+                '}' if quote.is_none() => {
+                    depth -= 1;
+                    code.push(c);
+                }
+                c => code.push(c),
+            }
+        }
+        if !code.is_empty() {
+            nodes.push(RdNode::Code(code));
+        }
+        Ok(nodes)
+    }
+}
+
+/// Formats the nodes of a section into the lines of its escaped body.
+///
+/// The wrapper macros must stand on their own lines.
+fn render(nodes: &[RdNode], config: &Config) -> Result<Vec<String>, String> {
+    let mut lines = vec![];
+    for (index, node) in nodes.iter().enumerate() {
+        match node {
+            RdNode::Code(code) if code.trim().is_empty() => {}
+            RdNode::Code(code) => {
+                let formatted = format_lf(code.trim(), config)?;
+                lines.extend(formatted.trim_end().split('\n').map(escape));
+            }
+            RdNode::Macro(name, arguments)
+                if WRAPPER_MACROS.contains(name) && arguments.len() == 1 =>
+            {
+                let is_on_own_line = (index == 0 || ends_with_line_break(&nodes[index - 1]))
+                    && nodes.get(index + 1).is_none_or(starts_with_line_break);
+                if !is_on_own_line {
+                    return Err(format!("\\{name} does not stand on its own lines"));
+                }
+                lines.push(format!("\\{name}{{"));
+                lines.extend(render(&arguments[0], config)?);
+                lines.push("}".to_string());
+            }
+            RdNode::Macro(name, _) => return Err(format!("the \\{name} macro is not supported")),
+        }
+    }
+    Ok(lines)
+}
+
+fn ends_with_line_break(node: &RdNode) -> bool {
+    matches!(node, RdNode::Code(code) if code.trim_end_matches([' ', '\t']).ends_with('\n'))
+}
+
+fn starts_with_line_break(node: &RdNode) -> bool {
+    matches!(node, RdNode::Code(code) if code.trim_start_matches([' ', '\t']).starts_with('\n'))
+}
+
+/// Escapes a line of R code for the R-like text of Rd.
+///
+/// The braces in the strings and comments are escaped,
+/// because they need not be balanced.
+fn escape(line: &str) -> String {
+    let mut escaped = String::with_capacity(line.len());
+    let mut quote = None;
+    let mut is_comment = false;
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            // Rd keeps a lone backslash before a quote, as in 'it\'s'
+            '\\' if quote.is_some() => match chars.next() {
+                Some(next @ ('\'' | '"')) => {
+                    escaped.push(c);
+                    escaped.push(next);
+                }
+                Some(next @ ('\\' | '%' | '{' | '}')) => {
+                    escaped.push_str("\\\\\\");
+                    escaped.push(next);
+                }
+                Some(next) => {
+                    escaped.push_str("\\\\");
+                    escaped.push(next);
+                }
+                None => escaped.push_str("\\\\"),
+            },
+            '\\' | '%' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            '{' | '}' if quote.is_some() || is_comment => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            c if quote == Some(c) => {
+                quote = None;
+                escaped.push(c);
+            }
+            '"' | '\'' | '`' if quote.is_none() && !is_comment => {
+                quote = Some(c);
+                escaped.push(c);
+            }
+            '#' if quote.is_none() => {
+                is_comment = true;
+                escaped.push(c);
+            }
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
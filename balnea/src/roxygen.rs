@@ -0,0 +1,289 @@
+use std::collections::HashSet;
+
+use log::{debug, warn};
+use tokenizer::{Token, Tokenizer};
+
+use crate::{Config, format_lf};
+
+/// The tags whose content is prose that can be wrapped.
+const PROSE_TAGS: [&str; 13] = [
+    "param",
+    "return",
+    "returns",
+    "title",
+    "description",
+    "details",
+    "note",
+    "field",
+    "slot",
+    "seealso",
+    "references",
+    "format",
+    "source",
+];
+
+/// The tags whose content is code, so only the space after the tag
+/// is normalised.
+const CODE_TAGS: [&str; 7] = [
+    "examplesIf",
+    "eval",
+    "evalRd",
+    "evalNamespace",
+    "usage",
+    "rawRd",
+    "rawNamespace",
+];
+
+/// Formats the roxygen blocks of the formatted code.
+///
+/// The spaces in the tags are normalised, the paragraphs of prose
+/// with a line longer than the line length are wrapped and the code
+/// of `@examples` and `@examplesIf` is formatted. The examples which
+/// cannot be formatted are reported and left as they are.
+///
+/// Only the lines starting with a comment token are roxygen lines,
+/// so the `#'` lines of multi-line strings are kept as they are.
+pub(crate) fn format_roxygen(code: &str, config: &Config) -> String {
+    let comment_lines = match comment_lines(code) {
+        Ok(comment_lines) => comment_lines,
+        Err(error) => {
+            debug!("Skipping the roxygen blocks of code which does not tokenize: {error}");
+            return code.to_string();
+        }
+    };
+    let lines: Vec<&str> = code.split('\n').collect();
+    let roxygen_content = |index: usize| {
+        let line = lines.get(index)?;
+        comment_lines
+            .contains(&index)
+            .then(|| roxygen_content(line))
+            .flatten()
+    };
+    let mut formatted: Vec<String> = Vec::with_capacity(lines.len());
+    let mut index = 0;
+    while index < lines.len() {
+        if roxygen_content(index).is_none() {
+            formatted.push(lines[index].to_string());
+            index += 1;
+            continue;
+        }
+        let line = lines[index];
+        let block = RoxygenBlock {
+            indent: &line[..line.len() - line.trim_start().len()],
+            first_line: index + 1,
+            config,
+        };
+        let mut contents = vec![];
+        while let Some(content) =
+            roxygen_content(index).filter(|_| lines[index].starts_with(block.indent))
+        {
+            contents.push(content);
+            index += 1;
+        }
+        formatted.extend(
+            block
+                .format(&contents)
+                .iter()
+                .map(|content| block.render(content)),
+        );
+    }
+    formatted.join("\n")
+}
+
+/// Returns the indices of the lines starting with a comment.
+fn comment_lines(code: &str) -> Result<HashSet<usize>, String> {
+    let tokens = Tokenizer::new(code)
+        .tokenize()
+        .map_err(|e| format!("{e}"))?;
+    let line_ends: Vec<usize> = code.match_indices('\n').map(|(index, _)| index).collect();
+    Ok(tokens
+        .iter()
+        .filter(|token| matches!(token.token, Token::Comment(_)))
+        .map(|token| line_ends.partition_point(|&end| end < token.offset))
+        .collect())
+}
+
+/// Returns the content of a roxygen line after the `#'` and one space.
+fn roxygen_content(line: &str) -> Option<&str> {
+    let content = line.trim_start().strip_prefix("#'")?;
+    Some(content.strip_prefix(' ').unwrap_or(content))
+}
+
+/// Returns the name of the tag starting the content.
+fn tag(content: &str) -> Option<&str> {
+    let tag = content.strip_prefix('@')?;
+    Some(tag.split_whitespace().next().unwrap_or(tag))
+}
+
+struct RoxygenBlock<'a> {
+    indent: &'a str,
+    /// The number of the first line of the block, for reporting
+    first_line: usize,
+    config: &'a Config,
+}
+
+impl RoxygenBlock<'_> {
+    fn render(&self, content: &str) -> String {
+        if content.is_empty() {
+            format!("{}#'", self.indent)
+        } else {
+            format!("{}#' {}", self.indent, content)
+        }
+    }
+
+    /// The width available to the content of a line.
+    fn width(&self) -> usize {
+        (self.config.line_length.0 as usize).saturating_sub(self.render("").len() + 1)
+    }
+
+    fn format(&self, contents: &[&str]) -> Vec<String> {
+        let mut formatted = vec![];
+        let mut index = 0;
+        let mut is_fenced = false;
+        while index < contents.len() {
+            let content = contents[index];
+            index += 1;
+            if content.trim_start().starts_with("```") {
+                is_fenced = !is_fenced;
+            }
+            if is_fenced || content.trim_start().starts_with("```") {
+                formatted.push(content.to_string());
+                continue;
+            }
+            match tag(content) {
+                Some(examples_tag @ ("examples" | "examplesIf")) => {
+                    formatted.push(normalise_tag(content, examples_tag));
+                    let code_start = index;
+                    while contents.get(index).is_some_and(|line| tag(line).is_none()) {
+                        index += 1;
+                    }
+                    formatted
+                        .extend(self.format_examples(&contents[code_start..index], code_start));
+                }
+                Some(tag) if PROSE_TAGS.contains(&tag) => {
+                    let mut paragraph = vec![normalise_tag(content, tag)];
+                    while let Some(line) = contents.get(index).filter(|line| tag_continues(line)) {
+                        paragraph.push(line.to_string());
+                        index += 1;
+                    }
+                    formatted.extend(self.wrap(paragraph));
+                }
+                Some(tag) => formatted.push(normalise_tag(content, tag)),
+                None if is_prose(content) => {
+                    let mut paragraph = vec![content.to_string()];
+                    while let Some(line) = contents.get(index).filter(|line| is_prose(line)) {
+                        paragraph.push(line.to_string());
+                        index += 1;
+                    }
+                    formatted.extend(self.wrap(paragraph));
+                }
+                None => formatted.push(content.to_string()),
+            }
+        }
+        formatted
+    }
+
+    /// Wraps a paragraph if one of its lines is too long.
+    ///
+    /// The continuation lines keep the indentation
+    /// of the second line of the paragraph.
+    fn wrap(&self, paragraph: Vec<String>) -> Vec<String> {
+        let width = self.width();
+        if paragraph.iter().all(|line| line.chars().count() <= width) {
+            return paragraph;
+        }
+        let continuation_indent = paragraph
+            .get(1)
+            .map_or("", |line| &line[..line.len() - line.trim_start().len()]);
+        let mut wrapped: Vec<String> = vec![];
+        for word in paragraph.iter().flat_map(|line| line.split_whitespace()) {
+            match wrapped.last_mut() {
+                Some(line) if line.chars().count() + 1 + word.chars().count() <= width => {
+                    line.push(' ');
+                    line.push_str(word);
+                }
+                Some(_) => wrapped.push(format!("{continuation_indent}{word}")),
+                None => wrapped.push(word.to_string()),
+            }
+        }
+        wrapped
+    }
+
+    /// Formats the code of an example.
+    ///
+    /// `offset` is the index of the first line of the code in the block.
+    fn format_examples(&self, code: &[&str], offset: usize) -> Vec<String> {
+        let unchanged = || code.iter().map(|line| line.to_string()).collect();
+        let trailing_blank_lines = code
+            .iter()
+            .rev()
+            .take_while(|line| line.trim().is_empty())
+            .count();
+        let source = code[..code.len() - trailing_blank_lines].join("\n");
+        if source.trim().is_empty() {
+            return unchanged();
+        }
+        // The Rd macros, such as \dontrun{}, are not R code
+        if source.contains('\\') {
+            debug!(
+                "Skipping the roxygen examples with Rd macros at line {}",
+                self.first_line + offset
+            );
+            return unchanged();
+        }
+        let mut config = self.config.clone();
+        config.line_length.0 = self.width() as i32;
+        match format_lf(&source, &config) {
+            Ok(formatted) => formatted
+                .trim_end()
+                .split('\n')
+                .map(|line| line.to_string())
+                .chain(std::iter::repeat_n(String::new(), trailing_blank_lines))
+                .collect(),
+            Err(error) => {
+                warn!(
+                    "Could not format the roxygen examples at line {}: {error}",
+                    self.first_line + offset
+                );
+                unchanged()
+            }
+        }
+    }
+}
+
+/// Collapses the spaces of a tag line, such as `@param x  desc`.
+///
+/// Only the space after the tag of the code tags is normalised.
+fn normalise_tag(content: &str, tag: &str) -> String {
+    let rest = content["@".len() + tag.len()..].trim_start();
+    if rest.is_empty() {
+        format!("@{tag}")
+    } else if CODE_TAGS.contains(&tag) {
+        format!("@{tag} {}", rest.trim_end())
+    } else {
+        format!(
+            "@{tag} {}",
+            rest.split_whitespace().collect::<Vec<_>>().join(" ")
+        )
+    }
+}
+
+/// Returns whether the line continues the prose of the previous tag.
+fn tag_continues(line: &str) -> bool {
+    tag(line).is_none() && is_prose(line.trim_start())
+}
+
+/// Returns whether the line is prose that can be wrapped.
+///
+/// Markdown lists, headings, tables and Rd markup are not prose.
+fn is_prose(line: &str) -> bool {
+    let is_list_item = line.starts_with(['-', '*', '+'])
+        || line.split_once(['.', ')']).is_some_and(|(number, _)| {
+            !number.is_empty() && number.chars().all(|c| c.is_ascii_digit())
+        });
+    !line.trim().is_empty()
+        && tag(line).is_none()
+        && !is_list_item
+        && !line.starts_with(['#', '|', '>', '\\'])
+        && !line.starts_with("  ")
+}
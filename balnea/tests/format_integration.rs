@@ -1,6 +1,16 @@
 #![allow(clippy::field_reassign_with_default)]
-use formatter::config::{AllowNlAfterAssignment, EmbracingOpNoNl, Indent, LineLength};
-use tergo_lib::{Config, tergo_format};
+use formatter::config::{
+    AlignAssignments, AlignNamedArguments, AllowNlAfterAssignment, BlankLinesAroundFunctions,
+    ConvertMagrittrPipe, ElseOnNewLine, EmbracingOpNoNl, FormatRoxygen, FunctionBraceOnNewLine,
+    Indent, InsertFinalNewline, LineLength, MaxBlankLines, MaxBlankLinesTopLevel,
+    MaxPipeStepsOnOneLine, ReflowComments, RemoveRedundantBraces, RewriteRightAssign,
+    SpaceAfterCommentHash, SpaceInSpecialOps, SpacesAroundPower, SpacesAroundSlash,
+};
+use tergo_lib::{
+    AssignmentOperator, BraceStyle, CallArgumentsLayout, Config, EndOfLine, EnforceBraces,
+    QuoteStyle, SemicolonStatements, Style, tergo_format, tergo_format_markdown,
+    tergo_format_noweb, tergo_format_rd, tergo_format_rhtml,
+};
 
 fn log_init() {
     let res = simple_logger::init_with_env();
@@ -328,6 +338,203 @@ comparison_test!(multiple_subsets, "120");
 comparison_test!(comments_inline_with_assignment, "121");
 comparison_test!(comment_after_function_def, "122");
 comparison_test!(comment_in_binary_operations, "123");
+comparison_test!(semicolons_split_statements, "124", Config::default());
+comparison_test!(semicolons_keep_short_statements_inline, "125", {
+    let mut config = Config::default();
+    config.line_length = LineLength(60);
+    config.semicolon_statements = SemicolonStatements::Inline;
+    config
+});
+comparison_test!(accessors_and_formulas, "126");
+comparison_test!(
+    assignments_are_preserved_by_default,
+    "127",
+    Config::default()
+);
+comparison_test!(assignments_are_rewritten_to_left_arrows, "128", {
+    let mut config = Config::default();
+    config.assignment_operator = AssignmentOperator::LeftArrow;
+    config.rewrite_right_assign = RewriteRightAssign(true);
+    config
+});
+comparison_test!(quotes_are_rewritten_to_double_quotes, "129", {
+    let mut config = Config::default();
+    config.quote_style = QuoteStyle::Double;
+    config
+});
+comparison_test!(quotes_are_rewritten_to_single_quotes, "130", {
+    let mut config = Config::default();
+    config.quote_style = QuoteStyle::Single;
+    config
+});
+comparison_test!(blank_lines_are_capped, "131", Config::default());
+comparison_test!(blank_lines_are_added_around_functions, "132", {
+    let mut config = Config::default();
+    config.max_blank_lines = MaxBlankLines(2);
+    config.max_blank_lines_top_level = MaxBlankLinesTopLevel(1);
+    config.blank_lines_around_functions = BlankLinesAroundFunctions(1);
+    config
+});
+comparison_test!(call_arguments_one_per_line, "133", {
+    let mut config = Config::default();
+    config.line_length = LineLength(60);
+    config.call_arguments_layout = CallArgumentsLayout::OnePerLine;
+    config
+});
+comparison_test!(assignments_and_named_arguments_are_aligned, "134", {
+    let mut config = Config::default();
+    config.line_length = LineLength(60);
+    config.align_assignments = AlignAssignments(true);
+    config.align_named_arguments = AlignNamedArguments(true);
+    config
+});
+comparison_test!(operator_spacing_is_configurable, "135", {
+    let mut config = Config::default();
+    config.spaces_around_power = SpacesAroundPower(true);
+    config.spaces_around_slash = SpacesAroundSlash(false);
+    config.space_in_special_ops = SpaceInSpecialOps(false);
+    config
+});
+comparison_test!(brace_styles_are_configurable, "136", {
+    let mut config = Config::default();
+    config.brace_style = BraceStyle::Allman;
+    config.else_on_new_line = ElseOnNewLine(true);
+    config.function_brace_on_new_line = FunctionBraceOnNewLine(true);
+    config
+});
+comparison_test!(braces_are_enforced, "137", {
+    let mut config = Config::default();
+    config.enforce_braces = EnforceBraces::Multiline;
+    config.remove_redundant_braces = RemoveRedundantBraces(true);
+    config
+});
+comparison_test!(
+    tidyverse_style_preset,
+    "138",
+    Config::from_style(Style::Tidyverse)
+);
+comparison_test!(
+    bioconductor_style_preset,
+    "139",
+    Config::from_style(Style::Bioconductor)
+);
+comparison_test!(
+    google_style_preset,
+    "140",
+    Config::from_style(Style::Google)
+);
+comparison_test!(pipe_chains_are_laid_out, "141", {
+    let mut config = Config::default();
+    config.max_pipe_steps_on_one_line = MaxPipeStepsOnOneLine(Some(1));
+    config.convert_magrittr_pipe = ConvertMagrittrPipe(true);
+    config
+});
+comparison_test!(comments_are_normalised_and_reflowed, "142", {
+    let mut config = Config::default();
+    config.line_length = LineLength(60);
+    config.space_after_comment_hash = SpaceAfterCommentHash(true);
+    config.reflow_comments = ReflowComments(true);
+    config
+});
+comparison_test!(roxygen_blocks_are_formatted, "143", {
+    let mut config = Config::default();
+    config.line_length = LineLength(60);
+    config.format_roxygen = FormatRoxygen(true);
+    config
+});
+comparison_test!(roxygen_lines_in_strings_are_kept, "149", {
+    let mut config = Config::default();
+    config.line_length = LineLength(60);
+    config.format_roxygen = FormatRoxygen(true);
+    config
+});
+
+#[test]
+fn r_markdown_chunks_are_formatted() {
+    log_init();
+    let input = include_str!("test_cases/144.Rmd");
+    let expected = include_str!("test_cases/144.expected");
+    let result = tergo_format_markdown(input, Some(&Config::default()));
+    assert_formatting_eq(&result, expected);
+}
+
+#[test]
+fn r_markdown_chunks_keep_crlf_line_endings() {
+    log_init();
+    let input = include_str!("test_cases/144.Rmd").replace('\n', "\r\n");
+    let expected = include_str!("test_cases/144.expected").replace('\n', "\r\n");
+    let result = tergo_format_markdown(&input, Some(&Config::default()));
+    assert_formatting_eq(&result, &expected);
+}
+
+#[test]
+fn r_markdown_chunks_inside_other_fences_are_kept() {
+    log_init();
+    let input = include_str!("test_cases/151.Rmd");
+    let expected = include_str!("test_cases/151.expected");
+    let result = tergo_format_markdown(input, Some(&Config::default()));
+    assert_formatting_eq(&result, expected);
+}
+
+#[test]
+fn noweb_chunks_are_formatted() {
+    log_init();
+    let input = include_str!("test_cases/147.Rnw");
+    let expected = include_str!("test_cases/147.expected");
+    let result = tergo_format_noweb(input, Some(&Config::default()));
+    assert_formatting_eq(&result, expected);
+}
+
+#[test]
+fn rhtml_chunks_are_formatted() {
+    log_init();
+    let input = include_str!("test_cases/148.Rhtml");
+    let expected = include_str!("test_cases/148.expected");
+    let result = tergo_format_rhtml(input, Some(&Config::default()));
+    assert_formatting_eq(&result, expected);
+}
+
+#[test]
+fn rd_code_sections_are_formatted() {
+    log_init();
+    let input = include_str!("test_cases/145.Rd");
+    let expected = include_str!("test_cases/145.expected");
+    let result = tergo_format_rd(input, Some(&Config::default()));
+    assert_formatting_eq(&result, expected);
+}
+
+#[test]
+fn rd_sections_with_unsupported_macros_are_kept() {
+    log_init();
+    let input = include_str!("test_cases/146.Rd");
+    let result = tergo_format_rd(input, Some(&Config::default()));
+    assert_formatting_eq(&result, input);
+}
+
+#[test]
+fn line_endings_follow_the_config() {
+    log_init();
+    let input = "f<-function(x){x}\r\ny<-1\r\n";
+    let mut config = Config::default();
+    config.end_of_line = EndOfLine::Crlf;
+    let result = tergo_format(input, Some(&config)).unwrap();
+    assert_formatting_eq(&result, "f <- function(x) {\r\n  x\r\n}\r\ny <- 1\r\n");
+
+    config.insert_final_newline = InsertFinalNewline(false);
+    let result = tergo_format(input, Some(&config)).unwrap();
+    assert_formatting_eq(&result, "f <- function(x) {\r\n  x\r\n}\r\ny <- 1");
+}
+
+#[test]
+fn rd_escaped_quotes_are_formatted_idempotently() {
+    log_init();
+    let input = include_str!("test_cases/150.Rd");
+    let expected = include_str!("test_cases/150.expected");
+    let result = tergo_format_rd(input, Some(&Config::default()));
+    assert_formatting_eq(&result, expected);
+    let reformatted = tergo_format_rd(&result, Some(&Config::default()));
+    assert_formatting_eq(&reformatted, &result);
+}
 
 // Tidyverse styleguide examples
 comparison_test!(tidyverse_commas, "tidyverse_style_guide_001");
@@ -397,7 +604,11 @@ comparison_test!(
 comparison_test!(
     tidyverse_collapse_whitespace_to_single_line,
     "tidyverse_style_guide_016",
-    Config::default()
+    {
+        let mut config = Config::default();
+        config.max_blank_lines_top_level = MaxBlankLinesTopLevel(1);
+        config
+    }
 );
 comparison_test!(
     tidyverse_curly_braces,
@@ -468,7 +679,11 @@ comparison_test!(
     "real_life_001",
     Config::default()
 );
-comparison_test!(rle_collapse_whiteline, "real_life_002", Config::default());
+comparison_test!(rle_collapse_whiteline, "real_life_002", {
+    let mut config = Config::default();
+    config.max_blank_lines_top_level = MaxBlankLinesTopLevel(1);
+    config
+});
 comparison_test!(
     rle_make_line_broke_funcs_fit_one_line,
     "real_life_003",
@@ -22,25 +22,98 @@ export async function activate(context: vscode.ExtensionContext) {
     wasmContext
   );
 
-  vscode.languages.registerDocumentFormattingEditProvider("r", {
-    provideDocumentFormattingEdits(
-      document,
-      _options,
-      _token
-    ): vscode.TextEdit[] {
-      let documentText = document.getText();
-      console.log(`Formatting the document:\n${documentText}`);
-      return [
-        vscode.TextEdit.replace(
-          new vscode.Range(
-            document.lineAt(0).range.start,
-            document.lineAt(document.lineCount - 1).range.end
-          ),
-          api.format(documentText)
-        ),
-      ];
-    },
-  });
+  const diagnostics = vscode.languages.createDiagnosticCollection("tergo");
+  context.subscriptions.push(diagnostics);
+
+  // Reports the formatting error at its range and returns no edits
+  function reportError(document: vscode.TextDocument, error: unknown): vscode.TextEdit[] {
+    if (!(error instanceof tergo.FormatError.Error_)) {
+      throw error;
+    }
+    const { message, range } = error.value;
+    console.log(`Could not format the document: ${message}`);
+    const location = range ? toRange(range) : new vscode.Range(0, 0, 0, 0);
+    diagnostics.set(document.uri, [
+      new vscode.Diagnostic(location, message, vscode.DiagnosticSeverity.Error),
+    ]);
+    return [];
+  }
+
+  context.subscriptions.push(
+    vscode.languages.registerDocumentFormattingEditProvider("r", {
+      async provideDocumentFormattingEdits(
+        document,
+        _options,
+        _token
+      ): Promise<vscode.TextEdit[]> {
+        const documentText = document.getText();
+        console.log(`Formatting the document:\n${documentText}`);
+        const config = await readConfig(document);
+        try {
+          const formatted = api.format(documentText, config);
+          diagnostics.delete(document.uri);
+          return [
+            vscode.TextEdit.replace(
+              new vscode.Range(
+                document.lineAt(0).range.start,
+                document.lineAt(document.lineCount - 1).range.end
+              ),
+              formatted
+            ),
+          ];
+        } catch (error) {
+          return reportError(document, error);
+        }
+      },
+    }),
+    vscode.languages.registerDocumentRangeFormattingEditProvider("r", {
+      async provideDocumentRangeFormattingEdits(
+        document,
+        range,
+        _options,
+        _token
+      ): Promise<vscode.TextEdit[]> {
+        const config = await readConfig(document);
+        try {
+          const edit = api.formatRange(document.getText(), config, {
+            start: range.start,
+            end: range.end,
+          });
+          diagnostics.delete(document.uri);
+          return [vscode.TextEdit.replace(toRange(edit.range), edit.text)];
+        } catch (error) {
+          return reportError(document, error);
+        }
+      },
+    })
+  );
+}
+
+function toRange(range: tergo.Range): vscode.Range {
+  return new vscode.Range(
+    range.start.line,
+    range.start.character,
+    range.end.line,
+    range.end.character
+  );
+}
+
+// Returns the content of the tergo.toml file of the workspace folder
+// of the document, or an empty configuration
+async function readConfig(document: vscode.TextDocument): Promise<string> {
+  const folder = vscode.workspace.getWorkspaceFolder(document.uri);
+  if (!folder) {
+    return "";
+  }
+  try {
+    const bytes = await vscode.workspace.fs.readFile(
+      vscode.Uri.joinPath(folder.uri, "tergo.toml")
+    );
+    return new TextDecoder().decode(bytes);
+  } catch {
+    console.log(`No tergo.toml in ${folder.uri}. Using the default configuration.`);
+    return "";
+  }
 }
 
 // This method is called when your extension is deactivated
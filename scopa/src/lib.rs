@@ -1,4 +1,10 @@
-use tergo_lib::tergo_format;
+// The generated export of format-range takes the flattened range
+#![allow(clippy::too_many_arguments)]
+
+use tergo_lib::{
+    Config, offset_to_position, position_to_offset, tergo_format, tergo_format_range,
+    tergo_syntax_error_range,
+};
 
 wit_bindgen::generate!({
     world: "tergo"
@@ -7,9 +13,67 @@ wit_bindgen::generate!({
 struct Tergo;
 
 impl Guest for Tergo {
-    fn format(code: String) -> Result<String, String> {
-        simple_logger::init_with_env().map_err(|err| format!("Error initializing logger: {:?}", err))?;
-        tergo_format(&code, None)
+    fn format(code: String, config: String) -> Result<String, FormatError> {
+        init_logger();
+        let config = parse_config(&config)?;
+        tergo_format(&code, Some(&config)).map_err(|message| formatting_error(&code, message))
+    }
+
+    fn format_range(code: String, config: String, range: Range) -> Result<Edit, FormatError> {
+        init_logger();
+        let config = parse_config(&config)?;
+        let range = position_to_offset(&code, range.start.into())
+            ..position_to_offset(&code, range.end.into());
+        let (range, text) = tergo_format_range(&code, range, Some(&config))
+            .map_err(|message| formatting_error(&code, message))?;
+        Ok(Edit {
+            range: to_range(&code, range),
+            text,
+        })
+    }
+}
+
+/// Initializes the logger on the first call. The later calls keep it.
+fn init_logger() {
+    let _ = simple_logger::init_with_env();
+}
+
+fn parse_config(config: &str) -> Result<Config, FormatError> {
+    toml::from_str(config).map_err(|error| FormatError {
+        message: format!("Invalid configuration: {error}"),
+        range: None,
+    })
+}
+
+/// Returns the error of formatting the code, located at its syntax error,
+/// if it has one.
+fn formatting_error(code: &str, message: String) -> FormatError {
+    let range = tergo_syntax_error_range(code).map(|range| to_range(code, range));
+    FormatError { message, range }
+}
+
+fn to_range(code: &str, range: std::ops::Range<usize>) -> Range {
+    Range {
+        start: offset_to_position(code, range.start).into(),
+        end: offset_to_position(code, range.end).into(),
+    }
+}
+
+impl From<tergo_lib::Position> for Position {
+    fn from(position: tergo_lib::Position) -> Self {
+        Self {
+            line: position.line,
+            character: position.character,
+        }
+    }
+}
+
+impl From<Position> for tergo_lib::Position {
+    fn from(position: Position) -> Self {
+        Self {
+            line: position.line,
+            character: position.character,
+        }
     }
 }
 
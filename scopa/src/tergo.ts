@@ -4,9 +4,45 @@
  *--------------------------------------------------------------------------------------------*/
 /* eslint-disable @typescript-eslint/ban-types */
 import * as $wcm from '@vscode/wasm-component-model';
-import type { i32, ptr, result } from '@vscode/wasm-component-model';
+import type { i32, ptr, result, u32 } from '@vscode/wasm-component-model';
 
 export namespace tergo {
+  /**
+   * A position in the code. The characters are UTF-16 code units,
+   * like the positions of the editor.
+   */
+  export type Position = {
+    line: u32;
+    character: u32;
+  };
+
+  export type Range = {
+    start: Position;
+    end: Position;
+  };
+
+  /**
+   * An error of formatting, with the range of the code causing it, if known.
+   */
+  export type FormatError = {
+    message: string;
+    range?: Range | undefined;
+  };
+  export namespace FormatError {
+    export class Error_ extends $wcm.ResultError<FormatError> {
+      constructor(value: FormatError) {
+        super(`FormatError: ${value.message}`, value);
+      }
+    }
+  }
+
+  /**
+   * The replacement of a range of the code.
+   */
+  export type Edit = {
+    range: Range;
+    text: string;
+  };
   export type Imports = {
     log: (msg: string) => void;
   };
@@ -18,9 +54,18 @@ export namespace tergo {
   }
   export type Exports = {
     /**
-     * @throws $wcm.wstring.Error
+     * Formats the code. The config is the content of a tergo.toml file,
+     * which may be empty.
+     *
+     * @throws tergo.FormatError.Error_
      */
-    format: (code: string) => string;
+    format: (code: string, config: string) => string;
+    /**
+     * Formats the top-level statements of the code overlapping the range.
+     *
+     * @throws tergo.FormatError.Error_
+     */
+    formatRange: (code: string, config: string, range: Range) => Edit;
   };
   export namespace Exports {
     export type Promisified = $wcm.$exports.Promisify<Exports>;
@@ -31,6 +76,22 @@ export namespace tergo {
 }
 
 export namespace tergo.$ {
+  export const Position = new $wcm.RecordType<tergo.Position>([
+    ['line', $wcm.u32],
+    ['character', $wcm.u32],
+  ]);
+  export const Range = new $wcm.RecordType<tergo.Range>([
+    ['start', Position],
+    ['end', Position],
+  ]);
+  export const FormatError = new $wcm.RecordType<tergo.FormatError>([
+    ['message', $wcm.wstring],
+    ['range', new $wcm.OptionType<tergo.Range>(Range)],
+  ]);
+  export const Edit = new $wcm.RecordType<tergo.Edit>([
+    ['range', Range],
+    ['text', $wcm.wstring],
+  ]);
   export namespace imports {
     export const log = new $wcm.FunctionType<tergo.Imports['log']>('log', [
       ['msg', $wcm.wstring],
@@ -39,7 +100,13 @@ export namespace tergo.$ {
   export namespace exports {
     export const format = new $wcm.FunctionType<tergo.Exports['format']>('format', [
       ['code', $wcm.wstring],
-    ], new $wcm.ResultType<string, string>($wcm.wstring, $wcm.wstring, $wcm.wstring.Error));
+      ['config', $wcm.wstring],
+    ], new $wcm.ResultType<string, tergo.FormatError>($wcm.wstring, FormatError, tergo.FormatError.Error_));
+    export const formatRange = new $wcm.FunctionType<tergo.Exports['formatRange']>('format-range', [
+      ['code', $wcm.wstring],
+      ['config', $wcm.wstring],
+      ['range', Range],
+    ], new $wcm.ResultType<tergo.Edit, tergo.FormatError>(Edit, FormatError, tergo.FormatError.Error_));
   }
 }
 export namespace tergo._ {
@@ -64,14 +131,16 @@ export namespace tergo._ {
   };
   export namespace exports {
     export const functions: Map<string, $wcm.FunctionType> = new Map([
-      ['format', $.exports.format]
+      ['format', $.exports.format],
+      ['formatRange', $.exports.formatRange]
     ]);
     export function bind(exports: Exports, context: $wcm.WasmContext): tergo.Exports {
       return $wcm.$exports.bind<tergo.Exports>(_, exports, context);
     }
   }
   export type Exports = {
-    'format': (code_ptr: i32, code_len: i32, result: ptr<result<string, string>>) => void;
+    'format': (code_ptr: i32, code_len: i32, config_ptr: i32, config_len: i32, result: ptr<result<string, tergo.FormatError>>) => void;
+    'format-range': (code_ptr: i32, code_len: i32, config_ptr: i32, config_len: i32, range_start_line: i32, range_start_character: i32, range_end_line: i32, range_end_character: i32, result: ptr<result<tergo.Edit, tergo.FormatError>>) => void;
   };
   export function bind(service: tergo.Imports, code: $wcm.Code, context?: $wcm.ComponentModelContext): Promise<tergo.Exports>;
   export function bind(service: tergo.Imports.Promisified, code: $wcm.Code, port: $wcm.RAL.ConnectionPort, context?: $wcm.ComponentModelContext): Promise<tergo.Exports.Promisified>;
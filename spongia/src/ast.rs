@@ -1,3 +1,4 @@
+use tokenizer::Token;
 use tokenizer::tokens::CommentedToken;
 
 use crate::Input;
@@ -18,8 +19,13 @@ pub enum Expression<'a> {
         Box<Expression<'a>>,
         Vec<(&'a CommentedToken<'a>, Box<Expression<'a>>)>,
     ),
-    Formula(&'a CommentedToken<'a>, Box<Expression<'a>>),
+    Formula(Formula<'a>),
+    NamespaceAccess(NamespaceAccess<'a>),
+    MemberAccess(MemberAccess<'a>),
+    SlotAccess(SlotAccess<'a>),
     Newline(&'a CommentedToken<'a>),
+    /// A semicolon separating statements and the newline following it, if any.
+    Semicolon(&'a CommentedToken<'a>, Option<&'a CommentedToken<'a>>),
     Whitespace(crate::Input<'a, 'a>),
     EOF(&'a CommentedToken<'a>),
     FunctionDef(FunctionDefinition<'a>),
@@ -46,8 +52,14 @@ impl std::fmt::Display for Expression<'_> {
                 f.write_fmt(format_args!("{} {} {}", left, Input(&[op]), right))
             }
             Expression::MultiBop(lhs, other) => f.write_fmt(format_args!("{} {:?}", lhs, other)),
-            Expression::Formula(tilde, term) => write!(f, "{} {}", tilde, term),
+            Expression::Formula(formula) => write!(f, "{}", formula),
+            Expression::NamespaceAccess(namespace_access) => write!(f, "{}", namespace_access),
+            Expression::MemberAccess(member_access) => write!(f, "{}", member_access),
+            Expression::SlotAccess(slot_access) => write!(f, "{}", slot_access),
             Expression::Newline(token) => f.write_fmt(format_args!("{}", Input(&[token]))),
+            Expression::Semicolon(semicolon, _) => {
+                f.write_fmt(format_args!("{}", Input(&[semicolon])))
+            }
             Expression::Whitespace(tokens) => f.write_fmt(format_args!("{}", Input(tokens))),
             Expression::EOF(token) => f.write_fmt(format_args!("{}", Input(&[token]))),
             Expression::FunctionDef(func_def) => f.write_fmt(format_args!("{}", func_def)),
@@ -75,6 +87,122 @@ impl std::fmt::Display for Expression<'_> {
     }
 }
 
+impl<'a> Expression<'a> {
+    /// Calls `f` on every token of this expression in the source order.
+    pub fn for_each_token<F: FnMut(&'a CommentedToken<'a>)>(&self, f: &mut F) {
+        match self {
+            Expression::Symbol(token)
+            | Expression::Literal(token)
+            | Expression::Comment(token)
+            | Expression::Newline(token)
+            | Expression::EOF(token)
+            | Expression::Break(token)
+            | Expression::Continue(token) => f(token),
+            Expression::Semicolon(semicolon, newline) => {
+                f(semicolon);
+                if let Some(newline) = newline {
+                    f(newline);
+                }
+            }
+            Expression::Whitespace(tokens) => tokens.iter().for_each(|token| f(token)),
+            Expression::Term(term) => {
+                if let Some(pre_delimiter) = term.pre_delimiters {
+                    f(pre_delimiter);
+                }
+                term.term.iter().for_each(|expr| expr.for_each_token(f));
+                if let Some(post_delimiter) = term.post_delimiters {
+                    f(post_delimiter);
+                }
+            }
+            Expression::Unary(op, expr) => {
+                f(op);
+                expr.for_each_token(f);
+            }
+            Expression::Formula(formula) => {
+                if let Some(lhs) = &formula.lhs {
+                    lhs.for_each_token(f);
+                }
+                f(formula.tilde);
+                formula.rhs.for_each_token(f);
+            }
+            Expression::NamespaceAccess(namespace_access) => {
+                namespace_access.namespace.for_each_token(f);
+                f(namespace_access.operator);
+                namespace_access.name.for_each_token(f);
+            }
+            Expression::MemberAccess(member_access) => {
+                member_access.object.for_each_token(f);
+                f(member_access.operator);
+                member_access.member.for_each_token(f);
+            }
+            Expression::SlotAccess(slot_access) => {
+                slot_access.object.for_each_token(f);
+                f(slot_access.operator);
+                slot_access.slot.for_each_token(f);
+            }
+            Expression::Bop(op, lhs, rhs) => {
+                lhs.for_each_token(f);
+                f(op);
+                rhs.for_each_token(f);
+            }
+            Expression::MultiBop(lhs, other) => {
+                lhs.for_each_token(f);
+                for (op, rhs) in other {
+                    f(op);
+                    rhs.for_each_token(f);
+                }
+            }
+            Expression::FunctionDef(function_def) => {
+                f(function_def.keyword);
+                function_def.arguments.for_each_token(f);
+                function_def.body.for_each_token(f);
+            }
+            Expression::LambdaFunction(lambda) => {
+                f(lambda.keyword);
+                lambda.args.for_each_token(f);
+                lambda.body.for_each_token(f);
+            }
+            Expression::IfExpression(if_expression) => {
+                if_expression.if_conditional.for_each_token(f);
+                for else_if in &if_expression.else_ifs {
+                    f(else_if.else_keyword);
+                    else_if.if_conditional.for_each_token(f);
+                }
+                if let Some(trailing_else) = &if_expression.trailing_else {
+                    f(trailing_else.else_keyword);
+                    trailing_else.body.for_each_token(f);
+                }
+            }
+            Expression::WhileExpression(while_expression) => {
+                f(while_expression.while_keyword);
+                while_expression.condition.for_each_token(f);
+                while_expression.body.for_each_token(f);
+            }
+            Expression::RepeatExpression(repeat_expression) => {
+                f(repeat_expression.repeat_keyword);
+                repeat_expression.body.for_each_token(f);
+            }
+            Expression::FunctionCall(function_call) => {
+                function_call.function_ref.for_each_token(f);
+                function_call.args.for_each_token(f);
+            }
+            Expression::SubsetExpression(subset_expression) => {
+                subset_expression.object_ref.for_each_token(f);
+                subset_expression.args.for_each_token(f);
+            }
+            Expression::ForLoopExpression(for_loop) => {
+                f(for_loop.keyword);
+                for_loop.left_delim.for_each_token(f);
+                for_loop.identifier.for_each_token(f);
+                f(for_loop.in_keyword);
+                for_loop.collection.for_each_token(f);
+                for_loop.right_delim.for_each_token(f);
+                for_loop.body.for_each_token(f);
+            }
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct ExpressionsBuffer<'a>(pub &'a [Expression<'a>]);
 impl std::fmt::Display for ExpressionsBuffer<'_> {
@@ -157,6 +285,29 @@ pub enum Arg<'a> {
     ), // Argument name, equal sign, comma
 }
 
+impl<'a> Arg<'a> {
+    /// Calls `f` on every token of this argument in the source order.
+    pub fn for_each_token<F: FnMut(&'a CommentedToken<'a>)>(&self, f: &mut F) {
+        match self {
+            Arg::Proper(xpr, comma) => {
+                if let Some(xpr) = xpr {
+                    xpr.for_each_token(f);
+                }
+                if let Some(comma) = comma {
+                    comma.for_each_token(f);
+                }
+            }
+            Arg::EmptyEqual(name, equal, comma) => {
+                name.for_each_token(f);
+                f(equal);
+                if let Some(comma) = comma {
+                    comma.for_each_token(f);
+                }
+            }
+        }
+    }
+}
+
 impl std::fmt::Display for Arg<'_> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -196,6 +347,19 @@ impl Delimiter<'_> {
     }
 }
 
+impl<'a> Delimiter<'a> {
+    /// Calls `f` on every token of this delimiter in the source order.
+    pub fn for_each_token<F: FnMut(&'a CommentedToken<'a>)>(&self, f: &mut F) {
+        match self {
+            Delimiter::Paren(single) | Delimiter::SingleBracket(single) => f(single),
+            Delimiter::DoubleBracket((first, second)) => {
+                f(first);
+                f(second);
+            }
+        }
+    }
+}
+
 impl std::fmt::Display for Delimiter<'_> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -228,6 +392,15 @@ impl<'a> Args<'a> {
     }
 }
 
+impl<'a> Args<'a> {
+    /// Calls `f` on every token of these arguments in the source order.
+    pub fn for_each_token<F: FnMut(&'a CommentedToken<'a>)>(&self, f: &mut F) {
+        self.left_delimeter.for_each_token(f);
+        self.args.iter().for_each(|arg| arg.for_each_token(f));
+        self.right_delimeter.for_each_token(f);
+    }
+}
+
 impl std::fmt::Display for Args<'_> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.write_fmt(format_args!(
@@ -317,6 +490,17 @@ impl std::fmt::Display for IfConditional<'_> {
     }
 }
 
+impl<'a> IfConditional<'a> {
+    /// Calls `f` on every token of this conditional in the source order.
+    pub fn for_each_token<F: FnMut(&'a CommentedToken<'a>)>(&self, f: &mut F) {
+        f(self.keyword);
+        f(self.left_delimiter);
+        self.condition.for_each_token(f);
+        f(self.right_delimiter);
+        self.body.for_each_token(f);
+    }
+}
+
 impl std::fmt::Display for ElseIfConditional<'_> {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         f.write_fmt(format_args!(
@@ -435,3 +619,85 @@ impl std::fmt::Display for Lambda<'_> {
         f.write_fmt(format_args!("{} {} {}", self.keyword, self.args, self.body))
     }
 }
+
+// Formula
+#[derive(Debug, Clone, PartialEq)]
+pub struct Formula<'a> {
+    /// The left hand side of a two-sided formula, e.g. `y` in `y ~ x`.
+    pub lhs: Option<Box<Expression<'a>>>,
+    pub tilde: &'a CommentedToken<'a>,
+    pub rhs: Box<Expression<'a>>,
+}
+
+impl std::fmt::Display for Formula<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(lhs) = &self.lhs {
+            write!(f, "{} ", lhs)?;
+        }
+        write!(f, "{} {}", self.tilde, self.rhs)
+    }
+}
+
+// Namespace access
+// pkg::name and pkg:::name
+#[derive(Debug, Clone, PartialEq)]
+pub struct NamespaceAccess<'a> {
+    pub namespace: Box<Expression<'a>>,
+    /// Either `::` or `:::`.
+    pub operator: &'a CommentedToken<'a>,
+    pub name: Box<Expression<'a>>,
+}
+
+impl<'a> NamespaceAccess<'a> {
+    /// Returns the name of the accessed package, if it is a plain symbol.
+    pub fn package(&self) -> Option<&'a str> {
+        match *self.namespace {
+            Expression::Symbol(CommentedToken {
+                token: Token::Symbol(package),
+                ..
+            }) => Some(package.trim_matches('`')),
+            _ => None,
+        }
+    }
+
+    /// Whether this accesses an internal object with `:::`.
+    pub fn is_internal(&self) -> bool {
+        matches!(self.operator.token, Token::NsGetInt)
+    }
+}
+
+impl std::fmt::Display for NamespaceAccess<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}{}{}", self.namespace, self.operator, self.name)
+    }
+}
+
+// Member access
+// object$member
+#[derive(Debug, Clone, PartialEq)]
+pub struct MemberAccess<'a> {
+    pub object: Box<Expression<'a>>,
+    pub operator: &'a CommentedToken<'a>,
+    pub member: Box<Expression<'a>>,
+}
+
+impl std::fmt::Display for MemberAccess<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}{}{}", self.object, self.operator, self.member)
+    }
+}
+
+// Slot access
+// object@slot
+#[derive(Debug, Clone, PartialEq)]
+pub struct SlotAccess<'a> {
+    pub object: Box<Expression<'a>>,
+    pub operator: &'a CommentedToken<'a>,
+    pub slot: Box<Expression<'a>>,
+}
+
+impl std::fmt::Display for SlotAccess<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}{}{}", self.object, self.operator, self.slot)
+    }
+}
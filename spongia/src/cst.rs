@@ -0,0 +1,395 @@
+//! Lossless concrete syntax tree.
+//!
+//! The regular parsing pipeline squeezes comments into the tokens and drops
+//! whitespace altogether, so the original source cannot be recovered from
+//! the abstract syntax tree. This module complements the abstract syntax tree
+//! with trivia: every byte of the input that is not a part of a token
+//! (whitespace, newlines, comments and semicolons) is attached to the token
+//! preceding or following it. Printing the tree reproduces the input exactly.
+//!
+//! # Examples
+//!
+//! ```
+//! use tergo_parser::{Input, cst::parse_lossless, pre_parse};
+//! use tokenizer::Tokenizer;
+//!
+//! let source = "a <- 1 # one\nb<-2;c\n";
+//! let mut tokens = Tokenizer::new(source).tokenize().unwrap();
+//! let tokens = pre_parse(&mut tokens);
+//! let tree = parse_lossless(source, Input(&tokens)).unwrap();
+//! assert_eq!(tree.to_string(), source);
+//! ```
+use std::ops::Range;
+
+use tokenizer::Token;
+use tokenizer::tokens::CommentedToken;
+
+use crate::{Input, ast::Expression, parser::parse};
+
+/// A piece of the source code that is not a part of any token.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Trivia<'a> {
+    /// A run of spaces, tabs and carriage returns not followed by a line feed.
+    Whitespace(&'a str),
+    /// A line feed, optionally preceded by a carriage return.
+    Newline(&'a str),
+    /// A comment including the leading `#`.
+    Comment(&'a str),
+    /// A statement separator.
+    Semicolon(&'a str),
+    /// Characters that the tokenizer skipped over.
+    Skipped(&'a str),
+}
+
+impl<'a> Trivia<'a> {
+    /// Returns the source text of this trivia.
+    pub fn text(&self) -> &'a str {
+        match self {
+            Trivia::Whitespace(text)
+            | Trivia::Newline(text)
+            | Trivia::Comment(text)
+            | Trivia::Semicolon(text)
+            | Trivia::Skipped(text) => text,
+        }
+    }
+}
+
+/// A token together with its trivia.
+///
+/// The trailing trivia of a token extends up to, but not including,
+/// the next newline. Everything from that newline onwards is the leading
+/// trivia of the following token.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CstToken<'a> {
+    pub leading_trivia: Vec<Trivia<'a>>,
+    pub token: &'a CommentedToken<'a>,
+    /// The source text of the token. It might differ from the token,
+    /// e.g. `T` is tokenized as `TRUE`.
+    pub text: &'a str,
+    pub trailing_trivia: Vec<Trivia<'a>>,
+}
+
+impl CstToken<'_> {
+    /// Returns the byte range of the token in the source, without the trivia.
+    pub fn span(&self) -> Range<usize> {
+        self.token.offset..self.token.offset + self.text.len()
+    }
+}
+
+impl std::fmt::Display for CstToken<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for trivia in &self.leading_trivia {
+            f.write_str(trivia.text())?;
+        }
+        f.write_str(self.text)?;
+        for trivia in &self.trailing_trivia {
+            f.write_str(trivia.text())?;
+        }
+        Ok(())
+    }
+}
+
+/// A lossless syntax tree of an R program.
+///
+/// Holds the abstract syntax tree along with every token of the program
+/// and its trivia. Displaying the tree prints the original source.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SyntaxTree<'a> {
+    pub source: &'a str,
+    /// The tokens in the source order. The last token is always `EOF`.
+    pub tokens: Vec<CstToken<'a>>,
+    pub expressions: Vec<Expression<'a>>,
+}
+
+impl<'a> SyntaxTree<'a> {
+    /// Returns the token starting at the given byte offset.
+    pub fn token_at(&self, offset: usize) -> Option<&CstToken<'a>> {
+        self.tokens
+            .binary_search_by_key(&offset, |cst_token| cst_token.token.offset)
+            .ok()
+            .map(|idx| &self.tokens[idx])
+    }
+
+    /// Returns the byte range of the expression in the source.
+    ///
+    /// The range starts at the first token of the expression and ends
+    /// after its last token, so it excludes the surrounding trivia.
+    pub fn span(&self, expression: &Expression<'a>) -> Option<Range<usize>> {
+        let mut first: Option<&CommentedToken<'a>> = None;
+        let mut last: Option<&CommentedToken<'a>> = None;
+        expression.for_each_token(&mut |token| {
+            if first.is_none_or(|first| token.offset < first.offset) {
+                first = Some(token);
+            }
+            if last.is_none_or(|last| token.offset > last.offset) {
+                last = Some(token);
+            }
+        });
+        let start = self.token_at(first?.offset)?.span().start;
+        let end = self.token_at(last?.offset)?.span().end;
+        Some(start..end)
+    }
+
+    /// Returns the source with the text of the expression replaced.
+    ///
+    /// Everything outside the expression, including all the trivia,
+    /// is preserved exactly.
+    pub fn replace(&self, expression: &Expression<'a>, replacement: &str) -> Option<String> {
+        let span = self.span(expression)?;
+        let mut new_source = String::with_capacity(self.source.len() + replacement.len());
+        new_source.push_str(&self.source[..span.start]);
+        new_source.push_str(replacement);
+        new_source.push_str(&self.source[span.end..]);
+        Some(new_source)
+    }
+}
+
+impl std::fmt::Display for SyntaxTree<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for token in &self.tokens {
+            write!(f, "{}", token)?;
+        }
+        Ok(())
+    }
+}
+
+/// Parses the tokens into a lossless syntax tree.
+///
+/// # Arguments
+///
+/// * `source` - the source code the tokens were produced from
+/// * `tokens` - the output of [`crate::pre_parse`] for the same source
+pub fn parse_lossless<'a>(
+    source: &'a str,
+    tokens: Input<'a, 'a>,
+) -> Result<SyntaxTree<'a>, String> {
+    let expressions = parse(tokens.clone())?;
+
+    let mut cst_tokens: Vec<CstToken<'a>> = vec![];
+    let mut pending_trivia = vec![];
+    let mut previous_end = 0;
+    for token in tokens
+        .iter()
+        .filter(|token| !matches!(token.token, Token::Newline | Token::Semicolon))
+    {
+        if token.offset < previous_end || token.offset > source.len() {
+            return Err(format!(
+                "Token {} at offset {} does not follow the previous token",
+                token, token.offset
+            ));
+        }
+        pending_trivia.extend(lex_trivia(&source[previous_end..token.offset]));
+        if let Some(previous) = cst_tokens.last_mut() {
+            let newline = pending_trivia
+                .iter()
+                .position(|trivia| matches!(trivia, Trivia::Newline(_)))
+                .unwrap_or(pending_trivia.len());
+            previous.trailing_trivia = pending_trivia.drain(..newline).collect();
+        }
+        let text = token_text(source, token).ok_or_else(|| {
+            format!(
+                "Token {} does not match the source at offset {}",
+                token, token.offset
+            )
+        })?;
+        previous_end = token.offset + text.len();
+        cst_tokens.push(CstToken {
+            leading_trivia: std::mem::take(&mut pending_trivia),
+            token,
+            text,
+            trailing_trivia: vec![],
+        });
+    }
+
+    match cst_tokens.last() {
+        Some(last) if matches!(last.token.token, Token::EOF) && previous_end == source.len() => {
+            Ok(SyntaxTree {
+                source,
+                tokens: cst_tokens,
+                expressions,
+            })
+        }
+        _ => Err("The tokens do not end with EOF at the end of the source".to_string()),
+    }
+}
+
+/// Returns the source text of the token or `None` if the source
+/// does not match the token.
+fn token_text<'a>(source: &'a str, token: &CommentedToken<'a>) -> Option<&'a str> {
+    let rest = &source[token.offset..];
+    let text = match token.token {
+        Token::Symbol(text)
+        | Token::Literal(text)
+        | Token::Special(text)
+        | Token::Comment(text)
+        | Token::InlineComment(text)
+            if rest.starts_with(text) =>
+        {
+            text
+        }
+        // T and F are tokenized as TRUE and FALSE
+        Token::Literal("TRUE") => "T",
+        Token::Literal("FALSE") => "F",
+        Token::Power if rest.starts_with("**") => "**",
+        Token::Symbol(_)
+        | Token::Literal(_)
+        | Token::Special(_)
+        | Token::Comment(_)
+        | Token::InlineComment(_) => return None,
+        Token::Semicolon => ";",
+        Token::Newline => "\n",
+        Token::LParen => "(",
+        Token::RParen => ")",
+        Token::LBrace => "{",
+        Token::RBrace => "}",
+        Token::LBracket => "[",
+        Token::RBracket => "]",
+        Token::Comma => ",",
+        Token::Continue => "continue",
+        Token::Break => "break",
+        Token::Stop => "stop",
+        Token::If => "if",
+        Token::Else => "else",
+        Token::While => "while",
+        Token::For => "for",
+        Token::Repeat => "repeat",
+        Token::In => "in",
+        Token::Function => "function",
+        Token::Lambda => "\\",
+        Token::LAssign => "<-",
+        Token::SuperAssign => "<<-",
+        Token::ColonAssign => ":=",
+        Token::RAssign => "->",
+        Token::OldAssign => "=",
+        Token::Equal => "==",
+        Token::NotEqual => "!=",
+        Token::LowerThan => "<",
+        Token::GreaterThan => ">",
+        Token::LowerEqual => "<=",
+        Token::GreaterEqual => ">=",
+        Token::Power => "^",
+        Token::Divide => "/",
+        Token::Multiply => "*",
+        Token::Minus => "-",
+        Token::Plus => "+",
+        Token::Help => "?",
+        Token::And => "&&",
+        Token::VectorizedAnd => "&",
+        Token::Or => "||",
+        Token::VectorizedOr => "|",
+        Token::Dollar => "$",
+        Token::Pipe => "|>",
+        Token::Modulo => "%%",
+        Token::NsGet => "::",
+        Token::NsGetInt => ":::",
+        Token::Tilde => "~",
+        Token::Colon => ":",
+        Token::Slot => "@",
+        Token::UnaryNot => "!",
+        Token::EOF => "",
+    };
+    rest.starts_with(text).then(|| &rest[..text.len()])
+}
+
+/// Splits the text between two tokens into trivia.
+fn lex_trivia(text: &str) -> Vec<Trivia<'_>> {
+    let mut trivia = vec![];
+    let mut rest = text;
+    while let Some(first) = rest.chars().next() {
+        let len = match first {
+            '\n' => 1,
+            '\r' if rest.starts_with("\r\n") => 2,
+            ';' => 1,
+            '#' => rest.find(['\r', '\n']).unwrap_or(rest.len()),
+            ' ' | '\t' | '\r' => rest
+                .find(|c: char| !matches!(c, ' ' | '\t' | '\r'))
+                .unwrap_or(rest.len()),
+            _ => rest
+                .find([' ', '\t', '\r', '\n', ';', '#'])
+                .unwrap_or(rest.len()),
+        };
+        let (piece, new_rest) = rest.split_at(len);
+        trivia.push(match first {
+            '\n' | '\r' if piece.ends_with('\n') => Trivia::Newline(piece),
+            ';' => Trivia::Semicolon(piece),
+            '#' => Trivia::Comment(piece),
+            ' ' | '\t' | '\r' => Trivia::Whitespace(piece),
+            _ => Trivia::Skipped(piece),
+        });
+        rest = new_rest;
+    }
+    trivia
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pre_parse;
+    use tokenizer::Tokenizer;
+
+    fn round_trip(source: &str) -> String {
+        let mut tokens = Tokenizer::new(source).tokenize().unwrap();
+        let tokens = pre_parse(&mut tokens);
+        let tree = parse_lossless(source, Input(&tokens)).unwrap();
+        tree.to_string()
+    }
+
+    #[test]
+    fn round_trips_the_source() {
+        let sources = [
+            "",
+            "\n\n",
+            "a <- 1\n",
+            "a<-1;b   <- 2 ;  c\n",
+            "# leading\n\n\n# comment\nf <- function(x,   y = 2) {\n  x + y # inline\n}\n\n",
+            "if (T) {\n\tx ** 2\n} else F\n",
+            "x <- r\"(raw # string)\"; y <- 'a;b'\r\nz\r\n",
+            "library(pkg)\nx |>\n  f() %>%\n  g(a = , b)   \n# trailing comment",
+            "x[[1]][2]$a@b\n\\(x) x\nfor (i in 1:10) {\n  next_one()\n}",
+        ];
+        for source in sources {
+            assert_eq!(round_trip(source), source);
+        }
+    }
+
+    #[test]
+    fn attaches_trivia_up_to_the_newline_as_trailing() {
+        let source = "a # comment\n\n  b";
+        let mut tokens = Tokenizer::new(source).tokenize().unwrap();
+        let tokens = pre_parse(&mut tokens);
+        let tree = parse_lossless(source, Input(&tokens)).unwrap();
+
+        assert_eq!(tree.tokens.len(), 3);
+        assert_eq!(
+            tree.tokens[0].trailing_trivia,
+            vec![Trivia::Whitespace(" "), Trivia::Comment("# comment")]
+        );
+        assert_eq!(
+            tree.tokens[1].leading_trivia,
+            vec![
+                Trivia::Newline("\n"),
+                Trivia::Newline("\n"),
+                Trivia::Whitespace("  ")
+            ]
+        );
+        assert_eq!(tree.tokens[1].text, "b");
+    }
+
+    #[test]
+    fn replaces_a_single_expression() {
+        let source = "# keep me\nx <- foo(1,  2)   # and me\ny";
+        let mut tokens = Tokenizer::new(source).tokenize().unwrap();
+        let tokens = pre_parse(&mut tokens);
+        let tree = parse_lossless(source, Input(&tokens)).unwrap();
+
+        let Expression::MultiBop(_, rhs) = &tree.expressions[0] else {
+            panic!("Expected an assignment, got: {}", tree.expressions[0]);
+        };
+        let rhs = &rhs[0].1;
+        assert_eq!(tree.span(rhs), Some(15..25));
+        assert_eq!(
+            tree.replace(rhs, "bar()").unwrap(),
+            "# keep me\nx <- bar()   # and me\ny"
+        );
+    }
+}
@@ -11,7 +11,12 @@ use tokenizer::{Token::*, tokens::CommentedToken};
 use crate::ast::Args;
 use crate::ast::Delimiter;
 use crate::ast::Expression;
+use crate::ast::Formula;
 use crate::ast::FunctionCall;
+use crate::ast::MemberAccess;
+use crate::ast::NamespaceAccess;
+use crate::ast::SlotAccess;
+use crate::ast::SubsetExpression;
 use crate::ast::TermExpr;
 use crate::compound::delimited_comma_sep_exprs;
 use crate::compound::for_loop_expression;
@@ -94,7 +99,11 @@ pub(crate) fn unary_term<'a, 'b: 'a>(
     trace!("unary_term: got tokens: {}", InputForDisplay(&tokens));
     alt((
         map((tilde, expr), |(tilde, term)| {
-            Expression::Formula(tilde, Box::new(term))
+            Expression::Formula(Formula {
+                lhs: None,
+                tilde,
+                rhs: Box::new(term),
+            })
         }),
         map((unary_op, unary_term), |(op, term)| {
             Expression::Unary(op, Box::new(term))
@@ -114,7 +123,13 @@ pub(crate) fn unary_term_with_newlines<'a, 'b: 'a>(
     alt((
         map(
             (tilde, many0(newline), expr_with_newlines),
-            |(tilde, _, term)| Expression::Formula(tilde, Box::new(term)),
+            |(tilde, _, term)| {
+                Expression::Formula(Formula {
+                    lhs: None,
+                    tilde,
+                    rhs: Box::new(term),
+                })
+            },
         ),
         map(
             (unary_op, many0(newline), unary_term_with_newlines),
@@ -164,7 +179,7 @@ pub(crate) fn atomic_term<'a, 'b: 'a>(
                 })
             }
             Tail::DoubleSubset(args) | Tail::SingleSubset(args) => {
-                acc = Expression::SubsetExpression(crate::ast::SubsetExpression {
+                acc = Expression::SubsetExpression(SubsetExpression {
                     object_ref: Box::new(acc),
                     args,
                 })
@@ -216,7 +231,7 @@ pub(crate) fn atomic_term_with_newlines<'a, 'b: 'a>(
                 })
             }
             Tail::DoubleSubset(args) | Tail::SingleSubset(args) => {
-                acc = Expression::SubsetExpression(crate::ast::SubsetExpression {
+                acc = Expression::SubsetExpression(SubsetExpression {
                     object_ref: Box::new(acc),
                     args,
                 })
@@ -437,7 +452,7 @@ where
                 let start = self.consumer.consume_newlines(0, &tokens);
                 lookahead = &tokens[start];
             }
-            lhs = Expression::Bop(op, Box::new(lhs), Box::new(rhs));
+            lhs = binary_expression(op, lhs, rhs);
             trace!(
                 "ExprParse: end of outer while loop: tokens {}",
                 InputForDisplay(&tokens)
@@ -489,6 +504,53 @@ pub(crate) fn expr_with_newlines<'a, 'b: 'a>(
     }
 }
 
+/// Builds the node for a binary operator.
+///
+/// Formulas and accessors get their dedicated nodes, everything else
+/// is a `Bop`. Calls and subsets bind tighter than the accessors in R,
+/// so `x$f(1)` is a call of `x$f` rather than an access to `f(1)`.
+fn binary_expression<'a>(
+    op: &'a CommentedToken<'a>,
+    lhs: Expression<'a>,
+    rhs: Expression<'a>,
+) -> Expression<'a> {
+    match (&op.token, rhs) {
+        (Dollar | Slot | NsGet | NsGetInt, Expression::FunctionCall(function_call)) => {
+            Expression::FunctionCall(FunctionCall {
+                function_ref: Box::new(binary_expression(op, lhs, *function_call.function_ref)),
+                args: function_call.args,
+            })
+        }
+        (Dollar | Slot | NsGet | NsGetInt, Expression::SubsetExpression(subset)) => {
+            Expression::SubsetExpression(SubsetExpression {
+                object_ref: Box::new(binary_expression(op, lhs, *subset.object_ref)),
+                args: subset.args,
+            })
+        }
+        (Dollar, rhs) => Expression::MemberAccess(MemberAccess {
+            object: Box::new(lhs),
+            operator: op,
+            member: Box::new(rhs),
+        }),
+        (Slot, rhs) => Expression::SlotAccess(SlotAccess {
+            object: Box::new(lhs),
+            operator: op,
+            slot: Box::new(rhs),
+        }),
+        (NsGet | NsGetInt, rhs) => Expression::NamespaceAccess(NamespaceAccess {
+            namespace: Box::new(lhs),
+            operator: op,
+            name: Box::new(rhs),
+        }),
+        (Tilde, rhs) => Expression::Formula(Formula {
+            lhs: Some(Box::new(lhs)),
+            tilde: op,
+            rhs: Box::new(rhs),
+        }),
+        (_, rhs) => Expression::Bop(op, Box::new(lhs), Box::new(rhs)),
+    }
+}
+
 fn bop_to_multibop(bop: Expression) -> Expression {
     match bop {
         Expression::Bop(op, lhs, rhs) => {
@@ -509,3 +571,79 @@ fn bop_to_multibop(bop: Expression) -> Expression {
         _ => bop,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse, pre_parse};
+    use tokenizer::Tokenizer;
+
+    fn parse_first(source: &str, check: impl FnOnce(&Expression)) {
+        let mut tokens = Tokenizer::new(source).tokenize().unwrap();
+        let tokens = pre_parse(&mut tokens);
+        let expressions = parse(Input(&tokens)).unwrap();
+        check(&expressions[0]);
+    }
+
+    #[test]
+    fn namespace_access_names_the_package() {
+        parse_first("pkg::fun", |expression| {
+            let Expression::NamespaceAccess(namespace_access) = expression else {
+                panic!("Expected a namespace access, got: {expression}");
+            };
+            assert_eq!(namespace_access.package(), Some("pkg"));
+            assert!(!namespace_access.is_internal());
+        });
+        parse_first("stats:::f", |expression| {
+            let Expression::NamespaceAccess(namespace_access) = expression else {
+                panic!("Expected a namespace access, got: {expression}");
+            };
+            assert_eq!(namespace_access.package(), Some("stats"));
+            assert!(namespace_access.is_internal());
+        });
+    }
+
+    #[test]
+    fn calls_bind_tighter_than_accessors() {
+        parse_first("pkg::fun(1)", |expression| {
+            let Expression::FunctionCall(call) = expression else {
+                panic!("Expected a function call, got: {expression}");
+            };
+            assert!(matches!(*call.function_ref, Expression::NamespaceAccess(_)));
+        });
+        parse_first("x$y[1]", |expression| {
+            let Expression::SubsetExpression(subset) = expression else {
+                panic!("Expected a subset, got: {expression}");
+            };
+            assert!(matches!(*subset.object_ref, Expression::MemberAccess(_)));
+        });
+    }
+
+    #[test]
+    fn accessors_are_left_associative() {
+        parse_first("x@y$z", |expression| {
+            let Expression::MemberAccess(member_access) = expression else {
+                panic!("Expected a member access, got: {expression}");
+            };
+            assert!(matches!(*member_access.object, Expression::SlotAccess(_)));
+            assert!(matches!(*member_access.member, Expression::Symbol(_)));
+        });
+    }
+
+    #[test]
+    fn formulas_have_an_optional_lhs() {
+        parse_first("~ x", |expression| {
+            let Expression::Formula(formula) = expression else {
+                panic!("Expected a formula, got: {expression}");
+            };
+            assert!(formula.lhs.is_none());
+        });
+        parse_first("y ~ x + z", |expression| {
+            let Expression::Formula(formula) = expression else {
+                panic!("Expected a formula, got: {expression}");
+            };
+            assert!(formula.lhs.is_some());
+            assert!(matches!(*formula.rhs, Expression::MultiBop(_, _)));
+        });
+    }
+}
@@ -0,0 +1,438 @@
+//! Incremental reparsing of an edited R program.
+//!
+//! [`reparse`] takes the top-level expressions of the previous parse and
+//! parses again only the statements around the edit. The statements before and
+//! after the edited region are moved onto the tokens of the edited source.
+
+use log::trace;
+use tokenizer::incremental::TextEdit;
+use tokenizer::tokens::CommentedToken;
+
+use crate::ast::{
+    Arg, Args, Delimiter, ElseIfConditional, Expression, ForLoop, Formula, FunctionCall,
+    FunctionDefinition, IfConditional, IfExpression, Lambda, MemberAccess, NamespaceAccess,
+    RepeatExpression, SlotAccess, SubsetExpression, TermExpr, TrailingElse, WhileExpression,
+};
+use crate::{Input, parse, parser::ParseError};
+
+/// Parses the edited source reusing the unchanged top-level expressions.
+///
+/// `previous` is the result of [`parse`] for the source before `edit`.
+/// `tokens` are the pre-parsed tokens of the edited source, for example
+/// obtained from [`tokenizer::incremental::relex`] and [`crate::pre_parse`].
+/// The result is the same as parsing `tokens` from scratch.
+pub fn reparse<'a>(
+    previous: &[Expression<'_>],
+    edit: &TextEdit<'_>,
+    tokens: Input<'a, 'a>,
+) -> Result<Vec<Expression<'a>>, ParseError> {
+    match reparse_reusing(previous, edit, tokens.clone())? {
+        Some((expressions, reused)) => {
+            trace!(
+                "Reused {reused} of {} top-level expressions",
+                expressions.len()
+            );
+            Ok(expressions)
+        }
+        None => {
+            trace!("Could not reuse the previous expressions. Parsing everything");
+            parse(tokens)
+        }
+    }
+}
+
+/// Returns the expressions and the number of reused expressions or `None`
+/// if the previous expressions do not match the edited tokens.
+fn reparse_reusing<'a>(
+    previous: &[Expression<'_>],
+    edit: &TextEdit<'_>,
+    tokens: Input<'a, 'a>,
+) -> Result<Option<(Vec<Expression<'a>>, usize)>, ParseError> {
+    let Some((Expression::EOF(_), statements)) = previous.split_last() else {
+        return Ok(None);
+    };
+    let starts = statements.iter().map(first_offset).collect::<Vec<_>>();
+
+    // The last statement starting before the edit might continue into
+    // the edited text, so parsing restarts at that statement.
+    let containing = starts
+        .iter()
+        .position(|start| start.is_some_and(|start| start >= edit.range.start))
+        .unwrap_or(statements.len());
+    let restart = (0..containing)
+        .rev()
+        .find(|&index| is_resync_point(&statements[index]) && starts[index].is_some())
+        .unwrap_or(0);
+    let restart_token = match starts.get(restart).copied().flatten() {
+        Some(offset) if restart > 0 => match token_index(&tokens, offset) {
+            Some(index) => index,
+            None => return Ok(None),
+        },
+        _ => 0,
+    };
+
+    let rebaser = Rebaser {
+        tokens: tokens.clone(),
+        edit,
+    };
+    let mut expressions = match rebaser.expressions(&statements[..restart]) {
+        Some(expressions) => expressions,
+        None => return Ok(None),
+    };
+    let mut reused = expressions.len();
+
+    let mut remaining = Input(&tokens[restart_token..]);
+    while !remaining.is_empty() && !matches!(remaining[0].token, tokenizer::Token::EOF) {
+        let (new_remaining, expression) = crate::program::statement_or_expr(remaining.clone())
+            .map_err(|err| ParseError::new(&remaining, err))?;
+        expressions.push(expression);
+        remaining = new_remaining;
+
+        // Once the parser reaches the start of an old statement after the edit,
+        // the rest of the old statements is the same.
+        let Some(next) = remaining.first() else {
+            break;
+        };
+        if next.offset < edit.new_end() {
+            continue;
+        }
+        let old_offset = next.offset - edit.new_end() + edit.range.end;
+        let Some(index) = starts[containing..]
+            .iter()
+            .position(|&start| start == Some(old_offset))
+            .map(|index| index + containing)
+        else {
+            continue;
+        };
+        if !is_resync_point(&statements[index]) {
+            continue;
+        }
+        match rebaser.expressions(&statements[index..]) {
+            Some(suffix) => {
+                reused += suffix.len();
+                expressions.extend(suffix);
+                remaining = Input(&tokens[tokens.len() - 1..]);
+                break;
+            }
+            None => return Ok(None),
+        }
+    }
+    match remaining.first() {
+        Some(eof) => expressions.push(Expression::EOF(eof)),
+        None => return Ok(None),
+    }
+    Ok(Some((expressions, reused)))
+}
+
+/// Statements starting with a token other than a newline begin at the same
+/// token regardless of the comments before them.
+fn is_resync_point(statement: &Expression) -> bool {
+    !matches!(statement, Expression::Whitespace(_) | Expression::EOF(_))
+}
+
+fn first_offset(expression: &Expression) -> Option<usize> {
+    let mut first = None;
+    expression.for_each_token(&mut |token| {
+        first.get_or_insert(token.offset);
+    });
+    first
+}
+
+fn token_index(tokens: &Input, offset: usize) -> Option<usize> {
+    tokens
+        .binary_search_by_key(&offset, |token| token.offset)
+        .ok()
+}
+
+/// Moves expressions of the previous parse onto the tokens of the edited source.
+struct Rebaser<'a, 'e> {
+    tokens: Input<'a, 'a>,
+    edit: &'e TextEdit<'e>,
+}
+
+impl<'a> Rebaser<'a, '_> {
+    fn token(&self, old: &CommentedToken) -> Option<&'a CommentedToken<'a>> {
+        let offset = self.edit.map_offset(old.offset)?;
+        let index = token_index(&self.tokens, offset)?;
+        let token = self.tokens.0[index];
+        (token.token == old.token).then_some(token)
+    }
+
+    fn tokens(&self, old: &Input) -> Option<Input<'a, 'a>> {
+        match old.first() {
+            None => Some(Input(&[])),
+            Some(first) => {
+                let offset = self.edit.map_offset(first.offset)?;
+                let start = token_index(&self.tokens, offset)?;
+                let tokens = self.tokens.0.get(start..start + old.len())?;
+                tokens
+                    .iter()
+                    .zip(old.iter())
+                    .all(|(new, old)| new.token == old.token)
+                    .then_some(Input(tokens))
+            }
+        }
+    }
+
+    fn optional_token(
+        &self,
+        old: Option<&CommentedToken>,
+    ) -> Option<Option<&'a CommentedToken<'a>>> {
+        match old {
+            Some(old) => self.token(old).map(Some),
+            None => Some(None),
+        }
+    }
+
+    fn expressions(&self, old: &[Expression]) -> Option<Vec<Expression<'a>>> {
+        old.iter()
+            .map(|expression| self.expression(expression))
+            .collect()
+    }
+
+    fn boxed(&self, old: &Expression) -> Option<Box<Expression<'a>>> {
+        self.expression(old).map(Box::new)
+    }
+
+    fn optional_expression(&self, old: &Option<Expression>) -> Option<Option<Expression<'a>>> {
+        match old {
+            Some(old) => self.expression(old).map(Some),
+            None => Some(None),
+        }
+    }
+
+    fn expression(&self, old: &Expression) -> Option<Expression<'a>> {
+        Some(match old {
+            Expression::Symbol(token) => Expression::Symbol(self.token(token)?),
+            Expression::Literal(token) => Expression::Literal(self.token(token)?),
+            Expression::Comment(token) => Expression::Comment(self.token(token)?),
+            Expression::Term(term) => Expression::Term(Box::new(TermExpr::new(
+                self.optional_token(term.pre_delimiters)?,
+                self.expressions(&term.term)?,
+                self.optional_token(term.post_delimiters)?,
+            ))),
+            Expression::Unary(operator, expression) => {
+                Expression::Unary(self.token(operator)?, self.boxed(expression)?)
+            }
+            Expression::Bop(operator, lhs, rhs) => {
+                Expression::Bop(self.token(operator)?, self.boxed(lhs)?, self.boxed(rhs)?)
+            }
+            Expression::MultiBop(lhs, rest) => Expression::MultiBop(
+                self.boxed(lhs)?,
+                rest.iter()
+                    .map(|(operator, rhs)| Some((self.token(operator)?, self.boxed(rhs)?)))
+                    .collect::<Option<_>>()?,
+            ),
+            Expression::Formula(formula) => Expression::Formula(Formula {
+                lhs: match &formula.lhs {
+                    Some(lhs) => Some(self.boxed(lhs)?),
+                    None => None,
+                },
+                tilde: self.token(formula.tilde)?,
+                rhs: self.boxed(&formula.rhs)?,
+            }),
+            Expression::NamespaceAccess(access) => Expression::NamespaceAccess(NamespaceAccess {
+                namespace: self.boxed(&access.namespace)?,
+                operator: self.token(access.operator)?,
+                name: self.boxed(&access.name)?,
+            }),
+            Expression::MemberAccess(access) => Expression::MemberAccess(MemberAccess {
+                object: self.boxed(&access.object)?,
+                operator: self.token(access.operator)?,
+                member: self.boxed(&access.member)?,
+            }),
+            Expression::SlotAccess(access) => Expression::SlotAccess(SlotAccess {
+                object: self.boxed(&access.object)?,
+                operator: self.token(access.operator)?,
+                slot: self.boxed(&access.slot)?,
+            }),
+            Expression::Newline(token) => Expression::Newline(self.token(token)?),
+            Expression::Semicolon(semicolon, newline) => {
+                Expression::Semicolon(self.token(semicolon)?, self.optional_token(*newline)?)
+            }
+            Expression::Whitespace(tokens) => Expression::Whitespace(self.tokens(tokens)?),
+            Expression::EOF(token) => Expression::EOF(self.token(token)?),
+            Expression::FunctionDef(function) => Expression::FunctionDef(FunctionDefinition::new(
+                self.token(function.keyword)?,
+                self.args(&function.arguments)?,
+                self.boxed(&function.body)?,
+            )),
+            Expression::LambdaFunction(lambda) => Expression::LambdaFunction(Lambda {
+                keyword: self.token(lambda.keyword)?,
+                args: self.args(&lambda.args)?,
+                body: self.boxed(&lambda.body)?,
+            }),
+            Expression::IfExpression(if_expression) => Expression::IfExpression(IfExpression {
+                if_conditional: self.if_conditional(&if_expression.if_conditional)?,
+                else_ifs: if_expression
+                    .else_ifs
+                    .iter()
+                    .map(|else_if| {
+                        Some(ElseIfConditional {
+                            else_keyword: self.token(else_if.else_keyword)?,
+                            if_conditional: self.if_conditional(&else_if.if_conditional)?,
+                        })
+                    })
+                    .collect::<Option<_>>()?,
+                trailing_else: match &if_expression.trailing_else {
+                    Some(trailing_else) => Some(TrailingElse {
+                        else_keyword: self.token(trailing_else.else_keyword)?,
+                        body: self.boxed(&trailing_else.body)?,
+                    }),
+                    None => None,
+                },
+            }),
+            Expression::WhileExpression(while_expression) => {
+                Expression::WhileExpression(WhileExpression {
+                    while_keyword: self.token(while_expression.while_keyword)?,
+                    condition: self.boxed(&while_expression.condition)?,
+                    body: self.boxed(&while_expression.body)?,
+                })
+            }
+            Expression::RepeatExpression(repeat) => {
+                Expression::RepeatExpression(RepeatExpression {
+                    repeat_keyword: self.token(repeat.repeat_keyword)?,
+                    body: self.boxed(&repeat.body)?,
+                })
+            }
+            Expression::FunctionCall(call) => Expression::FunctionCall(FunctionCall {
+                function_ref: self.boxed(&call.function_ref)?,
+                args: self.args(&call.args)?,
+            }),
+            Expression::SubsetExpression(subset) => {
+                Expression::SubsetExpression(SubsetExpression {
+                    object_ref: self.boxed(&subset.object_ref)?,
+                    args: self.args(&subset.args)?,
+                })
+            }
+            Expression::ForLoopExpression(for_loop) => Expression::ForLoopExpression(ForLoop {
+                keyword: self.token(for_loop.keyword)?,
+                left_delim: self.delimiter(&for_loop.left_delim)?,
+                identifier: self.boxed(&for_loop.identifier)?,
+                in_keyword: self.token(for_loop.in_keyword)?,
+                collection: self.boxed(&for_loop.collection)?,
+                right_delim: self.delimiter(&for_loop.right_delim)?,
+                body: self.boxed(&for_loop.body)?,
+            }),
+            Expression::Break(token) => Expression::Break(self.token(token)?),
+            Expression::Continue(token) => Expression::Continue(self.token(token)?),
+        })
+    }
+
+    fn if_conditional(&self, old: &IfConditional) -> Option<IfConditional<'a>> {
+        Some(IfConditional {
+            keyword: self.token(old.keyword)?,
+            left_delimiter: self.token(old.left_delimiter)?,
+            condition: self.boxed(&old.condition)?,
+            right_delimiter: self.token(old.right_delimiter)?,
+            body: self.boxed(&old.body)?,
+        })
+    }
+
+    fn delimiter(&self, old: &Delimiter) -> Option<Delimiter<'a>> {
+        Some(match old {
+            Delimiter::Paren(token) => Delimiter::Paren(self.token(token)?),
+            Delimiter::SingleBracket(token) => Delimiter::SingleBracket(self.token(token)?),
+            Delimiter::DoubleBracket((first, second)) => {
+                Delimiter::DoubleBracket((self.token(first)?, self.token(second)?))
+            }
+        })
+    }
+
+    fn args(&self, old: &Args) -> Option<Args<'a>> {
+        Some(Args::new(
+            self.delimiter(&old.left_delimeter)?,
+            old.args
+                .iter()
+                .map(|arg| {
+                    Some(match arg {
+                        Arg::Proper(argument, comma) => Arg::Proper(
+                            self.optional_expression(argument)?,
+                            self.optional_expression(comma)?,
+                        ),
+                        Arg::EmptyEqual(name, equal, comma) => Arg::EmptyEqual(
+                            self.expression(name)?,
+                            self.token(equal)?,
+                            self.optional_expression(comma)?,
+                        ),
+                    })
+                })
+                .collect::<Option<_>>()?,
+            self.delimiter(&old.right_delimeter)?,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pre_parse;
+    use tokenizer::Tokenizer;
+    use tokenizer::incremental::relex;
+
+    fn assert_reparse_matches_full_parse(source: &str, edit: TextEdit, expected_reused: usize) {
+        let old_tokens = Tokenizer::new(source).tokenize().unwrap();
+        let mut old_commented_tokens = old_tokens.clone();
+        let old_commented_tokens = pre_parse(&mut old_commented_tokens);
+        let previous = parse(Input(&old_commented_tokens)).unwrap();
+
+        let new_source = edit.apply(source);
+        let mut new_tokens = relex(&old_tokens, &edit, &new_source).unwrap();
+        let new_tokens = pre_parse(&mut new_tokens);
+        let (reparsed, reused) = reparse_reusing(&previous, &edit, Input(&new_tokens))
+            .unwrap()
+            .unwrap();
+
+        let mut full_tokens = Tokenizer::new(&new_source).tokenize().unwrap();
+        let full_tokens = pre_parse(&mut full_tokens);
+        let expected = parse(Input(&full_tokens)).unwrap();
+        assert_eq!(reparsed, expected, "Reparsing {source:?} with {edit:?}");
+        assert_eq!(
+            reused, expected_reused,
+            "Reparsing {source:?} with {edit:?}"
+        );
+    }
+
+    #[test]
+    fn reparse_matches_full_parse() {
+        let source =
+            "a <- 1\nb <- 2\n\n# comment\nf <- function(x) {\n  x + 1\n}\nc <- f(b)\nd <- 4\n";
+        let examples = [
+            // Changes a literal in the middle of the program
+            (TextEdit::new(12..13, "3"), 4),
+            // Extends a statement
+            (TextEdit::new(13..13, " + a"), 4),
+            // Edits the body of a function
+            (TextEdit::new(46..47, "y"), 4),
+            // Removes the newline between two statements
+            (TextEdit::new(6..7, ""), 4),
+            // Appends a statement
+            (TextEdit::new(source.len()..source.len(), "e <- 5\n"), 4),
+            // Prepends a statement
+            (TextEdit::new(0..0, "z <- 0\n"), 5),
+            // Removes a comment
+            (TextEdit::new(15..25, ""), 4),
+            // Adds a comment
+            (TextEdit::new(14..14, "# new\n"), 4),
+        ];
+        for (edit, expected_reused) in examples {
+            assert_reparse_matches_full_parse(source, edit, expected_reused);
+        }
+    }
+
+    #[test]
+    fn reparse_handles_edits_spanning_statements() {
+        let source = "if (a) {\n  b\n} else {\n  c\n}\nx <- 'one\ntwo'\ny\n";
+        let examples = [
+            // Replaces the whole program
+            (TextEdit::new(0..source.len(), "z\n"), 0),
+            // Replaces the bodies of both branches
+            (TextEdit::new(9..26, "d\n} else {\n  e\n"), 2),
+            // Removes the closing quote so that the string runs until the end
+            (TextEdit::new(41..42, ""), 1),
+        ];
+        for (edit, expected_reused) in examples {
+            assert_reparse_matches_full_parse(source, edit, expected_reused);
+        }
+    }
+}
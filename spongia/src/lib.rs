@@ -1,12 +1,14 @@
 pub mod ast;
 pub(crate) mod compound;
+pub mod cst;
 pub(crate) mod expressions;
+pub mod incremental;
 pub mod parser;
 pub(crate) mod pre_parsing_hooks;
 use std::{iter::Cloned, slice::Iter};
 
 use nom::Needed;
-pub use parser::parse;
+pub use parser::{ParseError, parse};
 pub use pre_parsing_hooks::pre_parse;
 use tokenizer::tokens::CommentedToken;
 pub(crate) mod program;
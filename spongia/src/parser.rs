@@ -3,13 +3,54 @@ use tokenizer::Token;
 
 use crate::{Input, ast::Expression};
 
-pub fn parse<'a, 'b: 'a>(mut tokens: Input<'a, 'b>) -> Result<Vec<Expression<'a>>, String> {
+/// An error of parsing.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseError {
+    /// The byte offset in the source of the token which could not be parsed.
+    pub offset: usize,
+    pub message: String,
+}
+
+impl ParseError {
+    /// Creates the error of parsing the statement starting at `statement`.
+    pub(crate) fn new(
+        statement: &Input<'_, '_>,
+        error: nom::Err<nom::error::Error<Input<'_, '_>>>,
+    ) -> Self {
+        let failed_token = match &error {
+            nom::Err::Error(error) | nom::Err::Failure(error) => error.input.first(),
+            nom::Err::Incomplete(_) => None,
+        };
+        Self {
+            offset: failed_token
+                .or(statement.first())
+                .map_or(0, |token| token.offset),
+            message: format!("Could not parse: {error}"),
+        }
+    }
+}
+
+impl std::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+impl From<ParseError> for String {
+    fn from(error: ParseError) -> Self {
+        error.message
+    }
+}
+
+pub fn parse<'a, 'b: 'a>(mut tokens: Input<'a, 'b>) -> Result<Vec<Expression<'a>>, ParseError> {
     let mut expressions = vec![];
 
     while !tokens.is_empty() && !matches!(tokens.first().unwrap().token, Token::EOF) {
         trace!("Main parse function, remaining tokens: {}", &tokens);
-        let (new_remaining_tokens, expr) = crate::program::statement_or_expr(tokens)
-            .map_err(|err| format!("Could not parse: {}", err))?;
+        let (new_remaining_tokens, expr) = crate::program::statement_or_expr(tokens.clone())
+            .map_err(|err| ParseError::new(&tokens, err))?;
         expressions.push(expr);
         tokens = new_remaining_tokens;
         trace!("Remaining tokens length: {}", &tokens.len());
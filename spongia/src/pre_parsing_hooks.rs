@@ -14,19 +14,20 @@ pub fn pre_parse<'a>(tokens: &'a mut [CommentedToken<'a>]) -> Vec<&'a CommentedT
     while it < tokens.len() {
         if let Token::Comment(comment) = tokens[it].token {
             let mut comments = vec![];
-            // Pop all newlines except the last one to collapse whitespace
-            // before comments. This handles such cases as:
+            // Pop all newlines except the last one and keep the blank lines
+            // as empty comments. This handles such cases as:
             // TRUE
             //
             //
             // # Leading comment
             // FALSE
-            if it > 1
-                && matches!(tokens[it - 1].token, Token::Newline)
-                && matches!(tokens[it - 2].token, Token::Newline)
-            {
-                comments.push("");
-            }
+            // The formatter caps the number of blank lines.
+            let newlines = tokens[..it]
+                .iter()
+                .rev()
+                .take_while(|token| matches!(token.token, Token::Newline))
+                .count();
+            comments.extend(std::iter::repeat_n("", newlines.saturating_sub(1)));
             comments.push(comment);
             while let Some(&last_added) = tokens_without_comments.last() {
                 let last_token: &CommentedToken<'a> = &tokens[last_added];
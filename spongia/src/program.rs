@@ -14,7 +14,10 @@ pub(crate) fn statement_or_expr<'a, 'b: 'a>(
 ) -> IResult<Input<'a, 'b>, Expression<'a>> {
     trace!("statement_or_expr: {}", tokens);
     alt((
-        map((expr, opt(alt((semicolon, newline)))), |(expr, _)| expr),
+        map((expr, opt(newline)), |(expr, _)| expr),
+        map((semicolon, opt(newline)), |(semicolon, newline)| {
+            Expression::Semicolon(semicolon, newline)
+        }),
         map(whitespace_or_comment, Expression::Whitespace),
     ))
     .parse(tokens)
@@ -0,0 +1,121 @@
+//! An on-disk cache of the files known to be formatted.
+//!
+//! The cache maps the paths of the files to the keys of their contents.
+//! A key hashes the content of a file, the kind of the file, the version
+//! of `tergo` and the resolved configuration, so a file is formatted again
+//! whenever any of them changes.
+
+use std::{
+    collections::HashMap,
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use log::{debug, warn};
+use sha2::{Digest, Sha256};
+use tergo_lib::Config;
+
+use crate::FileKind;
+
+const CACHE_DIRECTORY: &str = ".tergo_cache";
+const INDEX: &str = "index";
+
+pub(crate) struct Cache {
+    directory: PathBuf,
+    entries: HashMap<PathBuf, String>,
+    is_changed: bool,
+}
+
+impl Cache {
+    /// Opens the cache stored in the directory, or an empty cache
+    /// if there is none.
+    pub(crate) fn open(directory: &Path) -> Self {
+        let directory = directory.join(CACHE_DIRECTORY);
+        let entries = match std::fs::read_to_string(directory.join(INDEX)) {
+            Ok(index) => index
+                .lines()
+                .filter_map(|line| line.split_once(' '))
+                .map(|(key, path)| (PathBuf::from(path), key.to_string()))
+                .collect(),
+            Err(_) => {
+                debug!("No cache found in {directory:?}");
+                HashMap::new()
+            }
+        };
+        Self {
+            directory,
+            entries,
+            is_changed: false,
+        }
+    }
+
+    /// Returns whether the file had the key when it was last formatted.
+    pub(crate) fn is_formatted(&self, file: &Path, key: &str) -> bool {
+        std::path::absolute(file)
+            .is_ok_and(|file| self.entries.get(&file).is_some_and(|entry| entry == key))
+    }
+
+    /// Records that the file with the key is formatted.
+    pub(crate) fn insert(&mut self, file: &Path, key: String) {
+        let Ok(file) = std::path::absolute(file) else {
+            return;
+        };
+        // The index has a line per file
+        if file.to_str().is_none_or(|path| path.contains('\n')) {
+            return;
+        }
+        if self.entries.get(&file) != Some(&key) {
+            self.entries.insert(file, key);
+            self.is_changed = true;
+        }
+    }
+
+    /// Writes the cache to its directory, if it changed.
+    pub(crate) fn save(&self) {
+        if !self.is_changed {
+            return;
+        }
+        if let Err(error) = self.write() {
+            warn!(
+                "Failed to write the cache to {:?}. Error: {error}",
+                self.directory
+            );
+        }
+    }
+
+    fn write(&self) -> std::io::Result<()> {
+        std::fs::create_dir_all(&self.directory)?;
+        std::fs::write(self.directory.join(".gitignore"), "*\n")?;
+        // Replacing the index keeps it whole if another run writes it too
+        let temporary_index = self
+            .directory
+            .join(format!("{INDEX}.{}", std::process::id()));
+        let mut index = std::fs::File::create(&temporary_index)?;
+        for (file, key) in &self.entries {
+            if let Some(path) = file.to_str() {
+                writeln!(index, "{key} {path}")?;
+            }
+        }
+        index.sync_all()?;
+        std::fs::rename(temporary_index, self.directory.join(INDEX))
+    }
+}
+
+/// Returns the key of the content of a file formatted with the configuration.
+pub(crate) fn key(file: &Path, content: &str, config: &Config) -> String {
+    let mut hasher = Sha256::new();
+    for part in [
+        env!("CARGO_PKG_VERSION"),
+        &format!("{:?}", FileKind::of(file)),
+        &format!("{config:?}"),
+        content,
+    ] {
+        hasher.update(part.len().to_le_bytes());
+        hasher.update(part);
+    }
+    hasher
+        .finalize()
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
@@ -0,0 +1,301 @@
+//! Reads the options of the `.editorconfig` files applying to a file.
+//!
+//! The `.editorconfig` files are looked up from the directory of the file
+//! up to the file with `root = true`. The sections of the closer files
+//! override the sections of the files further up, and the later sections
+//! of a file override the earlier ones, as in <https://editorconfig.org>.
+//!
+//! The properties map to the options of the configuration:
+//! * `indent_size`, or `tab_width` if `indent_size = tab`, to `indent`
+//! * `max_line_length` to `line_length`
+//! * `end_of_line` to `end_of_line`
+//! * `insert_final_newline` to `insert_final_newline`
+//!
+//! The options are a layer under `tergo.toml`: the keys of `tergo.toml`
+//! and the options set by its `style` preset take precedence.
+
+use std::{collections::HashMap, path::Path};
+
+use log::{debug, trace};
+use tergo_lib::Style;
+
+const EDITORCONFIG: &str = ".editorconfig";
+
+/// Returns the options of the `.editorconfig` files applying to the file,
+/// as the keys of a `tergo.toml` file.
+///
+/// The options set by the style preset are left out, so the preset
+/// takes precedence. The invalid values are ignored, as the unknown
+/// values of the properties should be.
+pub(crate) fn options(file: &Path, style: Option<Style>) -> toml::Table {
+    let properties = properties(file);
+    let mut options = toml::Table::new();
+    let integer = |key: &str| {
+        let value = properties.get(key)?;
+        value
+            .parse::<i32>()
+            .ok()
+            .filter(|&value| value > 0)
+            .map(i64::from)
+            .or_else(|| {
+                debug!("Ignoring the invalid {key} in {EDITORCONFIG}: {value}");
+                None
+            })
+    };
+    let indent = match properties.get("indent_size").map(String::as_str) {
+        Some("tab") => integer("tab_width"),
+        _ => integer("indent_size"),
+    };
+    if let Some(indent) = indent {
+        options.insert("indent".to_string(), indent.into());
+    }
+    if properties.get("max_line_length").map(String::as_str) != Some("off") {
+        if let Some(line_length) = integer("max_line_length") {
+            options.insert("line_length".to_string(), line_length.into());
+        }
+    }
+    match properties.get("end_of_line").map(String::as_str) {
+        Some(end_of_line @ ("lf" | "crlf" | "cr")) => {
+            options.insert("end_of_line".to_string(), end_of_line.into());
+        }
+        Some(end_of_line) => {
+            debug!("Ignoring the invalid end_of_line in {EDITORCONFIG}: {end_of_line}")
+        }
+        None => {}
+    }
+    match properties.get("insert_final_newline").map(String::as_str) {
+        Some("true") => {
+            options.insert("insert_final_newline".to_string(), true.into());
+        }
+        Some("false") => {
+            options.insert("insert_final_newline".to_string(), false.into());
+        }
+        _ => {}
+    }
+    if let Some(style) = style {
+        options.retain(|key, _| !style.keys().contains(&key));
+    }
+    trace!("Options of {EDITORCONFIG} for {file:?}: {options:?}");
+    options
+}
+
+/// Returns the properties of the sections matching the file.
+///
+/// The names and values of the properties are lowercase.
+fn properties(file: &Path) -> HashMap<String, String> {
+    let Ok(file) = std::path::absolute(file) else {
+        return HashMap::new();
+    };
+    let mut editorconfigs = vec![];
+    for directory in file.ancestors().skip(1) {
+        let Ok(content) = std::fs::read_to_string(directory.join(EDITORCONFIG)) else {
+            continue;
+        };
+        let sections = parse(&content);
+        let is_root = sections[0].1.get("root").is_some_and(|root| root == "true");
+        editorconfigs.push((directory, sections));
+        if is_root {
+            break;
+        }
+    }
+    let mut properties = HashMap::new();
+    for (directory, sections) in editorconfigs.into_iter().rev() {
+        let Some(relative_path) = file.strip_prefix(directory).ok().and_then(Path::to_str) else {
+            continue;
+        };
+        let relative_path = relative_path.replace('\\', "/");
+        for (pattern, section) in sections.into_iter().skip(1) {
+            if pattern.is_some_and(|pattern| matches_section(&pattern, &relative_path)) {
+                properties.extend(section);
+            }
+        }
+    }
+    // The special value unset removes a property
+    properties.retain(|_, value| value != "unset");
+    properties
+}
+
+type Section = (Option<String>, HashMap<String, String>);
+
+/// Parses an `.editorconfig` file into its sections.
+///
+/// The first section holds the properties before the first section header,
+/// such as `root`, and has no pattern.
+fn parse(content: &str) -> Vec<Section> {
+    let mut sections: Vec<Section> = vec![(None, HashMap::new())];
+    for line in content.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with(['#', ';']) {
+            continue;
+        }
+        if let Some(pattern) = line
+            .strip_prefix('[')
+            .and_then(|line| line.strip_suffix(']'))
+        {
+            sections.push((Some(pattern.to_string()), HashMap::new()));
+        } else if let Some((key, value)) = line.split_once('=') {
+            let properties = &mut sections.last_mut().unwrap().1;
+            properties.insert(key.trim().to_lowercase(), value.trim().to_lowercase());
+        }
+    }
+    sections
+}
+
+/// Returns whether the section pattern matches the path relative
+/// to the directory of the `.editorconfig` file.
+///
+/// The patterns without a `/` match the file names in any directory.
+fn matches_section(pattern: &str, relative_path: &str) -> bool {
+    let pattern = if pattern.contains('/') {
+        pattern.trim_start_matches('/').to_string()
+    } else {
+        format!("**/{pattern}")
+    };
+    let path: Vec<char> = relative_path.chars().collect();
+    expand_braces(&pattern).iter().any(|pattern| {
+        let pattern: Vec<char> = pattern.chars().collect();
+        matches_glob(&pattern, &path)
+    })
+}
+
+/// Expands the `{a,b}` alternatives of a pattern into separate patterns.
+///
+/// The braces without a comma are kept as literal characters.
+fn expand_braces(pattern: &str) -> Vec<String> {
+    let mut depth = 0;
+    let mut open = None;
+    let mut commas = vec![];
+    for (index, c) in pattern.char_indices() {
+        match c {
+            '{' => {
+                if depth == 0 {
+                    open = Some(index);
+                    commas.clear();
+                }
+                depth += 1;
+            }
+            ',' if depth == 1 => commas.push(index),
+            '}' if depth > 0 => {
+                depth -= 1;
+                if let (0, Some(open)) = (depth, open) {
+                    if commas.is_empty() {
+                        continue;
+                    }
+                    let (prefix, suffix) = (&pattern[..open], &pattern[index + 1..]);
+                    let bounds = std::iter::once(open)
+                        .chain(commas.iter().copied())
+                        .chain(std::iter::once(index));
+                    let bounds: Vec<usize> = bounds.collect();
+                    return bounds
+                        .windows(2)
+                        .flat_map(|bound| {
+                            let alternative = &pattern[bound[0] + 1..bound[1]];
+                            expand_braces(&format!("{prefix}{alternative}{suffix}"))
+                        })
+                        .collect();
+                }
+            }
+            _ => {}
+        }
+    }
+    vec![pattern.to_string()]
+}
+
+/// Matches the whole path against a glob pattern without braces.
+///
+/// `*` matches any characters but `/`, `**` matches any characters,
+/// `?` matches a character but `/` and `[abc]`, `[!abc]` and `[a-z]`
+/// match the characters of a class.
+fn matches_glob(pattern: &[char], path: &[char]) -> bool {
+    match pattern {
+        [] => path.is_empty(),
+        ['*', '*', '/', rest @ ..] if matches_glob(rest, path) => true,
+        ['*', '*', rest @ ..] => (0..=path.len()).any(|index| matches_glob(rest, &path[index..])),
+        ['*', rest @ ..] => {
+            let segment_length = path.iter().take_while(|&&c| c != '/').count();
+            (0..=segment_length).any(|index| matches_glob(rest, &path[index..]))
+        }
+        ['?', rest @ ..] => matches!(path, [c, ..] if *c != '/') && matches_glob(rest, &path[1..]),
+        ['[', class @ ..] if class.contains(&']') => {
+            let end = class.iter().position(|&c| c == ']').unwrap();
+            let (class, rest) = (&class[..end], &class[end + 1..]);
+            let Some((&c, path_rest)) = path.split_first() else {
+                return false;
+            };
+            let (is_negated, class) = match class {
+                ['!', class @ ..] => (true, class),
+                class => (false, class),
+            };
+            let mut is_member = false;
+            let mut index = 0;
+            while index < class.len() {
+                if index + 2 < class.len() && class[index + 1] == '-' {
+                    is_member |= (class[index]..=class[index + 2]).contains(&c);
+                    index += 3;
+                } else {
+                    is_member |= class[index] == c;
+                    index += 1;
+                }
+            }
+            is_member != is_negated && c != '/' && matches_glob(rest, path_rest)
+        }
+        ['\\', escaped, rest @ ..] | [escaped, rest @ ..] => {
+            path.first() == Some(escaped) && matches_glob(rest, &path[1..])
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn patterns_without_a_slash_match_file_names_in_any_directory() {
+        assert!(matches_section("*.R", "script.R"));
+        assert!(matches_section("*.R", "R/script.R"));
+        assert!(matches_section("*", "R/script.R"));
+        assert!(!matches_section("*.R", "script.Rmd"));
+    }
+
+    #[test]
+    fn patterns_with_a_slash_match_relative_paths() {
+        assert!(matches_section("R/*.R", "R/script.R"));
+        assert!(matches_section("/R/*.R", "R/script.R"));
+        assert!(!matches_section("R/*.R", "inst/R/script.R"));
+        assert!(!matches_section("R/*.R", "R/internal/script.R"));
+        assert!(matches_section("R/**.R", "R/internal/script.R"));
+    }
+
+    #[test]
+    fn patterns_match_alternatives_and_character_classes() {
+        assert!(matches_section("*.{R,r}", "script.r"));
+        assert!(matches_section("*.{R,Rmd}", "report.Rmd"));
+        assert!(!matches_section("*.{R,Rmd}", "report.md"));
+        assert!(matches_section("*.[Rr]", "script.r"));
+        assert!(!matches_section("*.[!Rr]", "script.r"));
+        assert!(matches_section("script?.R", "script1.R"));
+        assert!(matches_section("{single}.R", "{single}.R"));
+    }
+
+    #[test]
+    fn the_later_sections_override_the_earlier_ones() {
+        let directory = std::env::temp_dir().join("tergo-editorconfig-sections");
+        std::fs::create_dir_all(directory.join("R")).unwrap();
+        std::fs::write(
+            directory.join(EDITORCONFIG),
+            "root = true\n\n[*]\nindent_size = 2\nmax_line_length = 100\n\n\
+             [R/*.R]\nindent_size = tab\ntab_width = 4\nend_of_line = CRLF\n\
+             insert_final_newline = false\nmax_line_length = off\n",
+        )
+        .unwrap();
+        let script = directory.join("R").join("script.R");
+        std::fs::write(&script, "").unwrap();
+
+        let options = options(&script, None);
+
+        assert_eq!(options["indent"].as_integer(), Some(4));
+        assert!(!options.contains_key("line_length"));
+        assert_eq!(options["end_of_line"].as_str(), Some("crlf"));
+        assert_eq!(options["insert_final_newline"].as_bool(), Some(false));
+    }
+}
@@ -0,0 +1,326 @@
+//! A language server formatting R code over stdio.
+//!
+//! The server formats whole documents, ranges of documents and the
+//! statements being typed, with the configuration of the `tergo.toml`
+//! file of the workspace folder containing the document, layered over
+//! the `.editorconfig` files applying to the document. It publishes
+//! the syntax errors of the open documents as diagnostics.
+
+use std::{
+    collections::HashMap,
+    error::Error,
+    ops::Range,
+    path::{Path, PathBuf},
+};
+
+use log::{debug, trace, warn};
+use lsp_server::{Connection, ErrorCode, Message, Notification, Request, Response};
+use lsp_types::{
+    Diagnostic, DiagnosticSeverity, DidChangeTextDocumentParams, DidCloseTextDocumentParams,
+    DidOpenTextDocumentParams, DocumentFormattingParams, DocumentOnTypeFormattingOptions,
+    DocumentOnTypeFormattingParams, DocumentRangeFormattingParams, InitializeParams, OneOf,
+    Position, PublishDiagnosticsParams, ServerCapabilities, TextDocumentSyncCapability,
+    TextDocumentSyncKind, TextEdit, Uri,
+    notification::{
+        DidChangeTextDocument, DidCloseTextDocument, DidOpenTextDocument,
+        Notification as NotificationTrait, PublishDiagnostics,
+    },
+    request::{Formatting, OnTypeFormatting, RangeFormatting, Request as RequestTrait},
+};
+use serde::de::DeserializeOwned;
+use tergo_lib::{Config, tergo_format, tergo_format_range, tergo_syntax_error_range};
+
+use crate::{get_file_config, read_config_file};
+
+type LspResult<T> = Result<T, Box<dyn Error + Send + Sync>>;
+
+/// Runs the language server until the client asks it to exit.
+pub(crate) fn run() -> LspResult<()> {
+    let (connection, io_threads) = Connection::stdio();
+    let capabilities = ServerCapabilities {
+        text_document_sync: Some(TextDocumentSyncCapability::Kind(TextDocumentSyncKind::FULL)),
+        document_formatting_provider: Some(OneOf::Left(true)),
+        document_range_formatting_provider: Some(OneOf::Left(true)),
+        document_on_type_formatting_provider: Some(DocumentOnTypeFormattingOptions {
+            first_trigger_character: "}".to_string(),
+            more_trigger_character: Some(vec!["\n".to_string()]),
+        }),
+        ..ServerCapabilities::default()
+    };
+    let params = connection.initialize(serde_json::to_value(capabilities)?)?;
+    let params: InitializeParams = serde_json::from_value(params)?;
+    Server::new(&connection, &params).serve()?;
+    // The writer thread finishes once the connection is dropped
+    drop(connection);
+    io_threads.join()?;
+    Ok(())
+}
+
+struct Server<'c> {
+    connection: &'c Connection,
+    workspace_folders: Vec<PathBuf>,
+    documents: HashMap<Uri, String>,
+}
+
+impl<'c> Server<'c> {
+    fn new(connection: &'c Connection, params: &InitializeParams) -> Self {
+        #[allow(deprecated)]
+        let root_uri = params.root_uri.iter();
+        let workspace_folders = params
+            .workspace_folders
+            .iter()
+            .flatten()
+            .map(|folder| &folder.uri)
+            .chain(root_uri)
+            .filter_map(uri_to_path)
+            .collect();
+        debug!("Workspace folders: {workspace_folders:?}");
+        Self {
+            connection,
+            workspace_folders,
+            documents: HashMap::new(),
+        }
+    }
+
+    fn serve(&mut self) -> LspResult<()> {
+        for message in &self.connection.receiver {
+            match message {
+                Message::Request(request) => {
+                    if self.connection.handle_shutdown(&request)? {
+                        return Ok(());
+                    }
+                    self.handle_request(request)?;
+                }
+                Message::Notification(notification) => self.handle_notification(notification)?,
+                Message::Response(_) => {}
+            }
+        }
+        Ok(())
+    }
+
+    fn handle_request(&mut self, request: Request) -> LspResult<()> {
+        trace!("Request: {}", request.method);
+        let params = request.params;
+        let edits = match request.method.as_str() {
+            Formatting::METHOD => {
+                serde_json::from_value(params).map(|params| self.format_document(&params))
+            }
+            RangeFormatting::METHOD => {
+                serde_json::from_value(params).map(|params| self.format_range(&params))
+            }
+            OnTypeFormatting::METHOD => {
+                serde_json::from_value(params).map(|params| self.format_on_type(&params))
+            }
+            method => {
+                let response = Response::new_err(
+                    request.id,
+                    ErrorCode::MethodNotFound as i32,
+                    format!("Unsupported request: {method}"),
+                );
+                return Ok(self.connection.sender.send(Message::Response(response))?);
+            }
+        };
+        let response = match edits {
+            Ok(edits) => Response::new_ok(request.id, edits),
+            Err(error) => Response::new_err(
+                request.id,
+                ErrorCode::InvalidParams as i32,
+                error.to_string(),
+            ),
+        };
+        Ok(self.connection.sender.send(Message::Response(response))?)
+    }
+
+    /// Handles a notification.
+    ///
+    /// The notifications with invalid parameters are logged and ignored,
+    /// because a notification has no response to report them.
+    fn handle_notification(&mut self, notification: Notification) -> LspResult<()> {
+        trace!("Notification: {}", notification.method);
+        match notification.method.as_str() {
+            DidOpenTextDocument::METHOD => {
+                let Some(params) = notification_params::<DidOpenTextDocumentParams>(notification)
+                else {
+                    return Ok(());
+                };
+                self.update_document(params.text_document.uri, params.text_document.text)
+            }
+            DidChangeTextDocument::METHOD => {
+                let Some(params) = notification_params::<DidChangeTextDocumentParams>(notification)
+                else {
+                    return Ok(());
+                };
+                match params.content_changes.into_iter().last() {
+                    Some(change) => self.update_document(params.text_document.uri, change.text),
+                    None => Ok(()),
+                }
+            }
+            DidCloseTextDocument::METHOD => {
+                let Some(params) = notification_params::<DidCloseTextDocumentParams>(notification)
+                else {
+                    return Ok(());
+                };
+                self.documents.remove(&params.text_document.uri);
+                self.publish_diagnostics(params.text_document.uri, vec![])
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn update_document(&mut self, uri: Uri, text: String) -> LspResult<()> {
+        let diagnostics = tergo_syntax_error_range(&text)
+            .map(|range| {
+                let token = &text[range.clone()];
+                let message = if token.is_empty() {
+                    "Syntax error at the end of the file".to_string()
+                } else {
+                    format!("Syntax error near `{token}`")
+                };
+                Diagnostic {
+                    range: to_lsp_range(&text, range),
+                    severity: Some(DiagnosticSeverity::ERROR),
+                    source: Some("tergo".to_string()),
+                    message,
+                    ..Diagnostic::default()
+                }
+            })
+            .into_iter()
+            .collect();
+        self.documents.insert(uri.clone(), text);
+        self.publish_diagnostics(uri, diagnostics)
+    }
+
+    fn publish_diagnostics(&self, uri: Uri, diagnostics: Vec<Diagnostic>) -> LspResult<()> {
+        let params = PublishDiagnosticsParams {
+            uri,
+            diagnostics,
+            version: None,
+        };
+        let notification = Notification::new(PublishDiagnostics::METHOD.to_string(), params);
+        Ok(self
+            .connection
+            .sender
+            .send(Message::Notification(notification))?)
+    }
+
+    /// Returns the configuration of the workspace folder containing the document.
+    ///
+    /// The innermost folder wins, if the workspace folders are nested.
+    fn config(&self, uri: &Uri) -> Config {
+        let Some(path) = uri_to_path(uri) else {
+            return Config::default();
+        };
+        let folder = self
+            .workspace_folders
+            .iter()
+            .filter(|folder| path.starts_with(folder))
+            .max_by_key(|folder| folder.components().count());
+        let config_options = match folder {
+            Some(folder) => read_config_file(&folder.join("tergo.toml")),
+            None => toml::Table::new(),
+        };
+        get_file_config(&config_options, &path)
+    }
+
+    fn format_document(&self, params: &DocumentFormattingParams) -> Option<Vec<TextEdit>> {
+        let uri = &params.text_document.uri;
+        let text = self.documents.get(uri)?;
+        let formatted = tergo_format(text, Some(&self.config(uri)))
+            .inspect_err(|error| debug!("Could not format {}: {error}", uri.as_str()))
+            .ok()?;
+        Some(edit(text, 0..text.len(), formatted))
+    }
+
+    fn format_range(&self, params: &DocumentRangeFormattingParams) -> Option<Vec<TextEdit>> {
+        let uri = &params.text_document.uri;
+        let text = self.documents.get(uri)?;
+        let range = position_to_offset(text, params.range.start)
+            ..position_to_offset(text, params.range.end);
+        self.format_statements(uri, text, range)
+    }
+
+    fn format_on_type(&self, params: &DocumentOnTypeFormattingParams) -> Option<Vec<TextEdit>> {
+        let uri = &params.text_document_position.text_document.uri;
+        let text = self.documents.get(uri)?;
+        let mut offset = position_to_offset(text, params.text_document_position.position);
+        if params.ch == "\n" {
+            // The statement ends on the line before the new line
+            offset = text[..offset].trim_end().len();
+        }
+        self.format_statements(uri, text, offset..offset)
+    }
+
+    fn format_statements(
+        &self,
+        uri: &Uri,
+        text: &str,
+        range: Range<usize>,
+    ) -> Option<Vec<TextEdit>> {
+        let (range, formatted) = tergo_format_range(text, range, Some(&self.config(uri)))
+            .inspect_err(|error| debug!("Could not format {}: {error}", uri.as_str()))
+            .ok()?;
+        Some(edit(text, range, formatted))
+    }
+}
+
+/// Returns the parameters of the notification, or None if they are invalid.
+fn notification_params<P: DeserializeOwned>(notification: Notification) -> Option<P> {
+    serde_json::from_value(notification.params)
+        .inspect_err(|error| warn!("Invalid {} notification: {error}", notification.method))
+        .ok()
+}
+
+/// Returns the edits replacing the range of the text, if the replacement differs.
+fn edit(text: &str, range: Range<usize>, replacement: String) -> Vec<TextEdit> {
+    if text[range.clone()] == replacement {
+        return vec![];
+    }
+    vec![TextEdit {
+        range: to_lsp_range(text, range),
+        new_text: replacement,
+    }]
+}
+
+fn to_lsp_range(text: &str, range: Range<usize>) -> lsp_types::Range {
+    lsp_types::Range {
+        start: offset_to_position(text, range.start),
+        end: offset_to_position(text, range.end),
+    }
+}
+
+/// Converts a byte offset into a position of LSP.
+fn offset_to_position(text: &str, offset: usize) -> Position {
+    let position = tergo_lib::offset_to_position(text, offset);
+    Position::new(position.line, position.character)
+}
+
+/// Converts a position of LSP into a byte offset.
+fn position_to_offset(text: &str, position: Position) -> usize {
+    let position = tergo_lib::Position {
+        line: position.line,
+        character: position.character,
+    };
+    tergo_lib::position_to_offset(text, position)
+}
+
+/// Returns the path of a `file:` URI.
+fn uri_to_path(uri: &Uri) -> Option<PathBuf> {
+    let path = uri.as_str().strip_prefix("file://")?;
+    let mut bytes = vec![];
+    let mut encoded = path.bytes();
+    while let Some(byte) = encoded.next() {
+        if byte == b'%' {
+            let hex = [encoded.next()?, encoded.next()?];
+            bytes.push(u8::from_str_radix(std::str::from_utf8(&hex).ok()?, 16).ok()?);
+        } else {
+            bytes.push(byte);
+        }
+    }
+    let path = String::from_utf8(bytes).ok()?;
+    // The Windows paths look like /C:/Users
+    let path = match path.strip_prefix('/') {
+        Some(windows_path) if windows_path.get(1..2) == Some(":") => windows_path.to_string(),
+        _ => path,
+    };
+    Some(Path::new(&path).to_path_buf())
+}
@@ -1,21 +1,48 @@
 use std::{
     ffi::OsStr,
+    fs::File,
+    io::Read,
     path::{Path, PathBuf},
     str::FromStr,
 };
 
-use clap::{Parser, arg};
+use cache::Cache;
+use clap::{Parser, Subcommand};
 use log::{debug, info, trace, warn};
-use tergo_lib::{Config, tergo_format};
+use tergo_lib::{
+    Config, Style, tergo_format, tergo_format_markdown, tergo_format_noweb, tergo_format_rd,
+    tergo_format_rhtml,
+};
+
+mod cache;
+mod editorconfig;
+mod lsp;
 
 #[derive(Parser, Debug)]
-#[command(version, about, long_about = None)]
+#[command(version, about, long_about = None, args_conflicts_with_subcommands = true)]
 struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+
     #[arg(default_value = ".")]
     path: String,
 
     #[arg(default_value = "tergo.toml")]
     config: String,
+
+    /// Also format the examples and usage sections of the Rd files
+    #[arg(long)]
+    rd: bool,
+
+    /// Format all files, without reading or writing the cache of formatted files
+    #[arg(long)]
+    no_cache: bool,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Run a language server over stdio
+    Lsp,
 }
 
 #[derive(Debug)]
@@ -37,74 +64,178 @@ impl std::fmt::Display for Error {
     }
 }
 
-fn get_config(path: &Path) -> Config {
+/// Returns the configuration of a file.
+///
+/// The options of the configuration file, including the options
+/// of its style preset, override the options of the `.editorconfig` files
+/// applying to the file.
+fn get_file_config(config_options: &toml::Table, file: &Path) -> Config {
+    let style = config_options
+        .get("style")
+        .and_then(|style| style.clone().try_into::<Style>().ok());
+    let mut options = editorconfig::options(file, style);
+    options.extend(config_options.clone());
+    // The options of .editorconfig never invalidate the configuration file
+    toml::Value::Table(options)
+        .try_into()
+        .unwrap_or_else(|_| resolve_config(config_options.clone()))
+}
+
+/// Reads the options of the configuration file.
+fn read_config_file(path: &Path) -> toml::Table {
     match std::fs::read_to_string(path) {
-        Ok(config_file) => {
-            let config: Config = toml::from_str(&config_file).unwrap_or_else(|_| {
-                warn!(
-                    "Failed to deserialize the configuration file to Config. Using the default \
-                     configuration."
-                );
-                Config::default()
-            });
-            config
-        }
+        Ok(config_file) => toml::from_str(&config_file).unwrap_or_else(|_| {
+            warn!("Failed to parse the configuration file. Using the default configuration.");
+            toml::Table::new()
+        }),
         Err(_) => {
             debug!("Configuration file not found. Using the default configuration.");
-            Config::default()
+            toml::Table::new()
         }
     }
 }
 
-fn format_file_in_place(path: &Path, config: &Config) -> Result<(), Error> {
+fn resolve_config(options: toml::Table) -> Config {
+    toml::Value::Table(options).try_into().unwrap_or_else(|_| {
+        warn!(
+            "Failed to deserialize the configuration file to Config. Using the default \
+             configuration."
+        );
+        Config::default()
+    })
+}
+
+fn format_file_in_place(
+    path: &Path,
+    config: &Config,
+    cache: Option<&mut Cache>,
+) -> Result<(), Error> {
     use Error::*;
     let content = std::fs::read_to_string(path).map_err(|e| {
         trace!("Error when reading the file {e}");
         ReadFileToString(path.to_str().unwrap_or("Error").to_owned())
     })?;
-    let formatted = tergo_format(&content, Some(config)).map_err(|e| {
-        trace!("Error when formatting: {e}");
-        Formatting(e)
-    })?;
+    if cache
+        .as_ref()
+        .is_some_and(|cache| cache.is_formatted(path, &cache::key(path, &content, config)))
+    {
+        debug!("Skipping the cached file: {path:?}");
+        return Ok(());
+    }
+    let formatted = match FileKind::of(path) {
+        Some(FileKind::Markdown) => tergo_format_markdown(&content, Some(config)),
+        Some(FileKind::Noweb) => tergo_format_noweb(&content, Some(config)),
+        Some(FileKind::Rhtml) => tergo_format_rhtml(&content, Some(config)),
+        Some(FileKind::Rd) => tergo_format_rd(&content, Some(config)),
+        Some(FileKind::R) | None => tergo_format(&content, Some(config)).map_err(|e| {
+            trace!("Error when formatting: {e}");
+            Formatting(e)
+        })?,
+    };
     trace!("Formatted code:\n:{}", formatted);
-    std::fs::write(path, formatted).map_err(|e| {
-        trace!("Error writing to file {e}");
-        WriteToFile(path.to_str().unwrap_or("Error").to_owned())
-    })?;
+    if formatted != content {
+        std::fs::write(path, &formatted).map_err(|e| {
+            trace!("Error writing to file {e}");
+            WriteToFile(path.to_str().unwrap_or("Error").to_owned())
+        })?;
+    }
+    if let Some(cache) = cache {
+        cache.insert(path, cache::key(path, &formatted, config));
+    }
     Ok(())
 }
 
-fn list_r_files(path: &Path) -> Vec<PathBuf> {
+/// The kinds of files with R code.
+#[derive(Debug, PartialEq)]
+enum FileKind {
+    R,
+    /// R Markdown and Quarto documents
+    Markdown,
+    /// Sweave and knitr LaTeX documents
+    Noweb,
+    /// knitr HTML documents
+    Rhtml,
+    /// R documentation files
+    Rd,
+}
+
+impl FileKind {
+    fn of(path: &Path) -> Option<Self> {
+        match path.extension().and_then(OsStr::to_str)? {
+            "R" | "r" => Some(Self::R),
+            "Rmd" | "rmd" | "qmd" | "Rmarkdown" => Some(Self::Markdown),
+            "Rnw" => Some(Self::Noweb),
+            "Rhtml" => Some(Self::Rhtml),
+            "Rd" => Some(Self::Rd),
+            _ => None,
+        }
+    }
+}
+
+/// Returns whether the file is an R script without a known extension,
+/// according to the `include` and `include_shebang_scripts` options.
+fn is_included_script(path: &Path, config: &Config) -> bool {
+    let Some(file_name) = path.file_name().and_then(OsStr::to_str) else {
+        return false;
+    };
+    config.include.matches(file_name)
+        || (config.include_shebang_scripts.0
+            && path.extension().is_none()
+            && has_rscript_shebang(path))
+}
+
+/// Returns whether the file starts with an `Rscript` shebang.
+///
+/// Only the first bytes are read, because the extension-less files
+/// are often binaries without line breaks.
+fn has_rscript_shebang(path: &Path) -> bool {
+    const SHEBANG_MAX_LENGTH: u64 = 128;
+    let Ok(file) = File::open(path) else {
+        return false;
+    };
+    let mut first_bytes = vec![];
+    if file
+        .take(SHEBANG_MAX_LENGTH)
+        .read_to_end(&mut first_bytes)
+        .is_err()
+    {
+        return false;
+    }
+    let first_line = first_bytes
+        .split(|&byte| byte == b'\n')
+        .next()
+        .unwrap_or_default();
+    first_line.starts_with(b"#!") && String::from_utf8_lossy(first_line).contains("Rscript")
+}
+
+fn list_r_files(path: &Path, config: &Config, include_rd: bool) -> Vec<PathBuf> {
     trace!("List R files in a path: {path:?}");
     match path.read_dir() {
         Ok(paths) => {
             let mut r_files = vec![];
             for path in paths.flatten() {
-                r_files.extend(list_r_files(&path.path()));
+                r_files.extend(list_r_files(&path.path(), config, include_rd));
             }
             r_files
         }
         Err(_) => {
             trace!("{path:?} is not a directory");
-            match path.extension() {
-                Some(extension) => {
-                    if extension == OsStr::new("R") || extension == OsStr::new("r") {
-                        vec![path.to_path_buf()]
-                    } else {
-                        vec![]
-                    }
-                }
-                None => {
-                    vec![]
-                }
+            match FileKind::of(path) {
+                Some(FileKind::Rd) if !include_rd => vec![],
+                Some(_) => vec![path.to_path_buf()],
+                None if is_included_script(path, config) => vec![path.to_path_buf()],
+                None => vec![],
             }
         }
     }
 }
 
-fn format_r_files(path: &Path, config_path: &Path) {
-    let r_files = list_r_files(path);
-    let config = get_config(config_path);
+fn format_r_files(path: &Path, config_path: &Path, include_rd: bool, use_cache: bool) {
+    let config_options = read_config_file(config_path);
+    // The cache lives next to the configuration file, at the root of the project
+    let mut cache = use_cache.then(|| Cache::open(config_path.parent().unwrap_or(Path::new(""))));
+    let config = resolve_config(config_options.clone());
+    let r_files = list_r_files(path, &config, include_rd);
     let ignored_paths: Vec<&Path> = config.exclusion_list.0.iter().map(Path::new).collect();
     debug!("Ignored paths: {ignored_paths:?}");
     for file in r_files {
@@ -116,7 +247,8 @@ fn format_r_files(path: &Path, config_path: &Path) {
             continue;
         }
         debug!("Formatting: {file:?}");
-        match format_file_in_place(&file, &config) {
+        let file_config = get_file_config(&config_options, &file);
+        match format_file_in_place(&file, &file_config, cache.as_mut()) {
             Ok(_) => info!("Formatted: {:?}", &file),
             Err(e) => {
                 warn!("Failed to format {:?}. Error: {e}", &file);
@@ -124,9 +256,22 @@ fn format_r_files(path: &Path, config_path: &Path) {
             }
         }
     }
+    if let Some(cache) = cache {
+        cache.save();
+    }
 }
 
 fn main() {
+    let cli = Cli::parse();
+    if let Some(Command::Lsp) = cli.command {
+        // The logger writes to stdout, which carries the protocol messages
+        if let Err(error) = lsp::run() {
+            eprintln!("The language server failed: {error}");
+            std::process::exit(1);
+        }
+        return;
+    }
+
     if std::env::var("RUST_LOG").is_err() {
         std::env::set_var("RUST_LOG", "info");
     }
@@ -134,9 +279,8 @@ fn main() {
         Ok(_) => {}
         Err(err) => println!("Failed to initialize logger: {:?}", err),
     }
-    let cli = Cli::parse();
 
     let path = PathBuf::from_str(&cli.path).unwrap();
     let config_path = PathBuf::from_str(&cli.config).unwrap();
-    format_r_files(&path, &config_path);
+    format_r_files(&path, &config_path, cli.rd, !cli.no_cache);
 }
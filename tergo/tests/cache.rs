@@ -0,0 +1,77 @@
+use std::{
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+/// Creates a project with an unformatted script.
+fn project(name: &str) -> PathBuf {
+    let project = std::env::temp_dir().join(format!("tergo-cache-{name}"));
+    let _ = std::fs::remove_dir_all(&project);
+    std::fs::create_dir_all(&project).unwrap();
+    std::fs::write(project.join("tergo.toml"), "").unwrap();
+    std::fs::write(project.join("script.R"), "x<-1\n").unwrap();
+    project
+}
+
+/// Runs `tergo` in the project and returns its output.
+fn run(project: &Path, args: &[&str]) -> String {
+    let output = Command::new(env!("CARGO_BIN_EXE_tergo"))
+        .args(args)
+        .current_dir(project)
+        .env("RUST_LOG", "debug")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn skips_the_files_formatted_by_a_previous_run() {
+    let project = project("skips");
+
+    let output = run(&project, &[]);
+    assert!(!output.contains("Skipping the cached file"));
+    assert_eq!(
+        std::fs::read_to_string(project.join("script.R")).unwrap(),
+        "x <- 1\n"
+    );
+    assert!(project.join(".tergo_cache").join("index").exists());
+
+    let output = run(&project, &[]);
+    assert!(output.contains("Skipping the cached file"));
+
+    std::fs::write(project.join("script.R"), "y<-2\n").unwrap();
+    let output = run(&project, &[]);
+    assert!(!output.contains("Skipping the cached file"));
+    assert_eq!(
+        std::fs::read_to_string(project.join("script.R")).unwrap(),
+        "y <- 2\n"
+    );
+}
+
+#[test]
+fn formats_again_when_the_config_changes() {
+    let project = project("config");
+    std::fs::write(project.join("script.R"), "f <- function(x) {\n  x\n}\n").unwrap();
+    run(&project, &[]);
+
+    std::fs::write(project.join("tergo.toml"), "indent = 4\n").unwrap();
+    let output = run(&project, &[]);
+    assert!(!output.contains("Skipping the cached file"));
+    assert_eq!(
+        std::fs::read_to_string(project.join("script.R")).unwrap(),
+        "f <- function(x) {\n    x\n}\n"
+    );
+}
+
+#[test]
+fn does_not_use_the_cache_with_no_cache() {
+    let project = project("no-cache");
+
+    run(&project, &["--no-cache"]);
+    assert!(!project.join(".tergo_cache").exists());
+
+    run(&project, &[]);
+    let output = run(&project, &["--no-cache"]);
+    assert!(!output.contains("Skipping the cached file"));
+}
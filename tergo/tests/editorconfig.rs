@@ -0,0 +1,78 @@
+use std::{path::PathBuf, process::Command};
+
+/// Creates a project with the configuration files and an unformatted script,
+/// formats it and returns the formatted script.
+fn format_project(name: &str, tergo_toml: &str, editorconfig: &str) -> String {
+    let project = std::env::temp_dir().join(format!("tergo-editorconfig-{name}"));
+    let _ = std::fs::remove_dir_all(&project);
+    std::fs::create_dir_all(&project).unwrap();
+    std::fs::write(project.join("tergo.toml"), tergo_toml).unwrap();
+    std::fs::write(project.join(".editorconfig"), editorconfig).unwrap();
+    let script: PathBuf = project.join("script.R");
+    std::fs::write(&script, "f<-function(x){x}\n").unwrap();
+    let output = Command::new(env!("CARGO_BIN_EXE_tergo"))
+        .arg("--no-cache")
+        .current_dir(&project)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    std::fs::read_to_string(script).unwrap()
+}
+
+#[test]
+fn editorconfig_sets_the_options_missing_from_tergo_toml() {
+    let formatted = format_project(
+        "options",
+        "",
+        "root = true\n\n[*.R]\nindent_size = 4\nend_of_line = crlf\n",
+    );
+    assert_eq!(formatted, "f <- function(x) {\r\n    x\r\n}\r\n");
+}
+
+#[test]
+fn tergo_toml_overrides_editorconfig() {
+    let formatted = format_project(
+        "override",
+        "indent = 2\n",
+        "root = true\n\n[*.R]\nindent_size = 4\n",
+    );
+    assert_eq!(formatted, "f <- function(x) {\n  x\n}\n");
+}
+
+#[test]
+fn the_style_preset_overrides_editorconfig() {
+    let formatted = format_project(
+        "style",
+        "style = \"bioconductor\"\n",
+        "root = true\n\n[*.R]\nindent_size = 2\nend_of_line = crlf\n",
+    );
+    assert_eq!(formatted, "f <- function(x) {\r\n    x\r\n}\r\n");
+}
+
+#[test]
+fn the_style_preset_overrides_editorconfig_with_default_values() {
+    let formatted = format_project(
+        "style-default",
+        "style = \"tidyverse\"\n",
+        "root = true\n\n[*.R]\nindent_size = 4\n",
+    );
+    assert_eq!(formatted, "f <- function(x) {\n  x\n}\n");
+}
+
+#[test]
+fn invalid_editorconfig_values_are_ignored() {
+    let formatted = format_project(
+        "invalid",
+        "indent = 8\n",
+        "root = true\n\n[*.R]\nend_of_line = native\nmax_line_length = 99999999999\n\
+         indent_size = -4\n",
+    );
+    assert_eq!(formatted, "f <- function(x) {\n        x\n}\n");
+
+    let formatted = format_project(
+        "invalid-indent",
+        "",
+        "root = true\n\n[*.R]\nindent_size = 0\nend_of_line = crlf\n",
+    );
+    assert_eq!(formatted, "f <- function(x) {\r\n  x\r\n}\r\n");
+}
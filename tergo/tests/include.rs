@@ -0,0 +1,43 @@
+use std::process::Command;
+
+const UNFORMATTED: &str = "x<-1\n";
+const FORMATTED: &str = "x <- 1\n";
+
+#[test]
+fn formats_the_included_files_and_the_rscript_scripts() {
+    let project = std::env::temp_dir().join("tergo-include");
+    let _ = std::fs::remove_dir_all(&project);
+    std::fs::create_dir_all(&project).unwrap();
+    std::fs::write(
+        project.join("tergo.toml"),
+        "include = [\".Rprofile\", \"Rprofile.site\"]\ninclude_shebang_scripts = true\n",
+    )
+    .unwrap();
+    let files = [
+        (".Rprofile", UNFORMATTED.to_string()),
+        ("Rprofile.site", UNFORMATTED.to_string()),
+        ("script", format!("#!/usr/bin/env Rscript\n{UNFORMATTED}")),
+        ("notes", UNFORMATTED.to_string()),
+        ("tool", format!("#!/bin/sh\n{UNFORMATTED}")),
+    ];
+    for (file, content) in &files {
+        std::fs::write(project.join(file), content).unwrap();
+    }
+
+    let output = Command::new(env!("CARGO_BIN_EXE_tergo"))
+        .arg("--no-cache")
+        .current_dir(&project)
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+
+    let read = |file: &str| std::fs::read_to_string(project.join(file)).unwrap();
+    assert_eq!(read(".Rprofile"), FORMATTED);
+    assert_eq!(read("Rprofile.site"), FORMATTED);
+    assert_eq!(
+        read("script"),
+        format!("#!/usr/bin/env Rscript\n{FORMATTED}")
+    );
+    assert_eq!(read("notes"), UNFORMATTED);
+    assert_eq!(read("tool"), format!("#!/bin/sh\n{UNFORMATTED}"));
+}
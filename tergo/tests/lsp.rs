@@ -0,0 +1,243 @@
+use std::{
+    io::{BufRead, BufReader, Read, Write},
+    path::PathBuf,
+    process::{Child, ChildStdin, ChildStdout, Command, Stdio},
+};
+
+use serde_json::{Value, json};
+
+/// A client talking to `tergo lsp` over stdio.
+struct LspClient {
+    server: Child,
+    stdin: ChildStdin,
+    stdout: BufReader<ChildStdout>,
+    next_id: i64,
+    workspace: PathBuf,
+}
+
+impl LspClient {
+    /// Starts the server in a new workspace folder with the config file.
+    fn start(name: &str, config: &str) -> Self {
+        let workspace = std::env::temp_dir().join(format!("tergo-lsp-{name}"));
+        std::fs::create_dir_all(&workspace).unwrap();
+        std::fs::write(workspace.join("tergo.toml"), config).unwrap();
+        let mut server = Command::new(env!("CARGO_BIN_EXE_tergo"))
+            .arg("lsp")
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap();
+        let mut client = Self {
+            stdin: server.stdin.take().unwrap(),
+            stdout: BufReader::new(server.stdout.take().unwrap()),
+            server,
+            next_id: 0,
+            workspace,
+        };
+        let workspace_uri = client.uri("");
+        client.request(
+            "initialize",
+            json!({
+                "capabilities": {},
+                "workspaceFolders": [{ "uri": workspace_uri, "name": name }],
+            }),
+        );
+        client.notify("initialized", json!({}));
+        client
+    }
+
+    fn uri(&self, file: &str) -> String {
+        format!("file://{}", self.workspace.join(file).display())
+    }
+
+    fn send(&mut self, message: Value) {
+        let content = message.to_string();
+        write!(
+            self.stdin,
+            "Content-Length: {}\r\n\r\n{content}",
+            content.len()
+        )
+        .unwrap();
+        self.stdin.flush().unwrap();
+    }
+
+    fn receive(&mut self) -> Value {
+        let mut content_length = 0;
+        loop {
+            let mut header = String::new();
+            self.stdout.read_line(&mut header).unwrap();
+            let header = header.trim();
+            if header.is_empty() {
+                break;
+            }
+            if let Some(length) = header.strip_prefix("Content-Length: ") {
+                content_length = length.parse().unwrap();
+            }
+        }
+        let mut content = vec![0; content_length];
+        self.stdout.read_exact(&mut content).unwrap();
+        serde_json::from_slice(&content).unwrap()
+    }
+
+    /// Sends a request and returns the result of its response.
+    fn request(&mut self, method: &str, params: Value) -> Value {
+        self.next_id += 1;
+        let id = self.next_id;
+        self.send(json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params }));
+        loop {
+            let message = self.receive();
+            if message["id"] == id {
+                return message["result"].clone();
+            }
+        }
+    }
+
+    fn notify(&mut self, method: &str, params: Value) {
+        self.send(json!({ "jsonrpc": "2.0", "method": method, "params": params }));
+    }
+
+    /// Returns the parameters of the next notification of the method.
+    fn wait_for_notification(&mut self, method: &str) -> Value {
+        loop {
+            let message = self.receive();
+            if message["method"] == method {
+                return message["params"].clone();
+            }
+        }
+    }
+
+    fn open(&mut self, file: &str, text: &str) -> Value {
+        let uri = self.uri(file);
+        self.notify(
+            "textDocument/didOpen",
+            json!({
+                "textDocument": { "uri": uri, "languageId": "r", "version": 1, "text": text },
+            }),
+        );
+        self.wait_for_notification("textDocument/publishDiagnostics")
+    }
+
+    fn shutdown(mut self) {
+        self.request("shutdown", Value::Null);
+        self.notify("exit", Value::Null);
+        assert!(self.server.wait().unwrap().success());
+    }
+}
+
+#[test]
+fn formats_documents_with_the_workspace_config() {
+    let mut client = LspClient::start("formatting", "indent = 4\n");
+    client.open("script.R", "f<-function(x){x}\n");
+    let uri = client.uri("script.R");
+
+    let edits = client.request(
+        "textDocument/formatting",
+        json!({
+            "textDocument": { "uri": uri },
+            "options": { "tabSize": 2, "insertSpaces": true },
+        }),
+    );
+
+    assert_eq!(edits[0]["newText"], "f <- function(x) {\n    x\n}\n");
+    assert_eq!(
+        edits[0]["range"]["end"],
+        json!({ "line": 1, "character": 0 })
+    );
+    client.shutdown();
+}
+
+#[test]
+fn formats_the_statements_in_a_range() {
+    let mut client = LspClient::start("range", "");
+    client.open("script.R", "a<-1\nb<-2\nc<-3\n");
+    let uri = client.uri("script.R");
+
+    let edits = client.request(
+        "textDocument/rangeFormatting",
+        json!({
+            "textDocument": { "uri": uri },
+            "range": {
+                "start": { "line": 1, "character": 0 },
+                "end": { "line": 1, "character": 2 },
+            },
+            "options": { "tabSize": 2, "insertSpaces": true },
+        }),
+    );
+
+    assert_eq!(
+        edits,
+        json!([{
+            "range": {
+                "start": { "line": 1, "character": 0 },
+                "end": { "line": 1, "character": 4 },
+            },
+            "newText": "b <- 2",
+        }])
+    );
+    client.shutdown();
+}
+
+#[test]
+fn formats_the_statement_on_type() {
+    let mut client = LspClient::start("on-type", "");
+    client.open("script.R", "x <- 1\nif(x){y}\n");
+    let uri = client.uri("script.R");
+
+    let edits = client.request(
+        "textDocument/onTypeFormatting",
+        json!({
+            "textDocument": { "uri": uri },
+            "position": { "line": 1, "character": 8 },
+            "ch": "}",
+            "options": { "tabSize": 2, "insertSpaces": true },
+        }),
+    );
+
+    assert_eq!(edits[0]["newText"], "if (x) {\n  y\n}");
+    assert_eq!(
+        edits[0]["range"]["start"],
+        json!({ "line": 1, "character": 0 })
+    );
+    client.shutdown();
+}
+
+#[test]
+fn publishes_syntax_errors_as_diagnostics() {
+    let mut client = LspClient::start("diagnostics", "");
+    let diagnostics = client.open("script.R", "x <- 1\nf(x))\n");
+
+    let diagnostic = &diagnostics["diagnostics"][0];
+    assert_eq!(
+        diagnostic["range"]["start"],
+        json!({ "line": 1, "character": 4 })
+    );
+    assert_eq!(diagnostic["severity"], 1);
+
+    let uri = client.uri("script.R");
+    client.notify(
+        "textDocument/didChange",
+        json!({
+            "textDocument": { "uri": uri, "version": 2 },
+            "contentChanges": [{ "text": "x <- 1\nf(x)\n" }],
+        }),
+    );
+    let diagnostics = client.wait_for_notification("textDocument/publishDiagnostics");
+    assert_eq!(diagnostics["diagnostics"], json!([]));
+    client.shutdown();
+}
+
+#[test]
+fn keeps_serving_after_invalid_notifications() {
+    let mut client = LspClient::start("invalid-notifications", "");
+    for method in [
+        "textDocument/didOpen",
+        "textDocument/didChange",
+        "textDocument/didClose",
+    ] {
+        client.notify(method, json!({ "textDocument": 42 }));
+    }
+
+    let diagnostics = client.open("script.R", "f(x))\n");
+    assert_eq!(diagnostics["diagnostics"].as_array().unwrap().len(), 1);
+    client.shutdown();
+}
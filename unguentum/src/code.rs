@@ -1,10 +1,17 @@
-use crate::format::CommonProperties;
+use crate::format::{CommonProperties, align_rows};
 use crate::{
-    config::{FormattingConfig, FunctionLineBreaks},
+    config::{
+        BraceStyle, CallArgumentsLayout, FormattingConfig, FunctionLineBreaks, SemicolonStatements,
+    },
     format::DocAlgebra,
+    pipes::{PipeSteps, pipe_chain},
+    quotes::normalise_quotes,
+    spacing::spaces_around_operator,
 };
 
-use parser::ast::{Arg, Args, Delimiter, Expression, IfConditional, TermExpr};
+use parser::ast::{
+    Arg, Args, Delimiter, Expression, FunctionDefinition, IfConditional, IfExpression, TermExpr,
+};
 use tokenizer::tokens::CommentedToken;
 
 use crate::format::{Doc, InlineCommentPosition, ShouldBreak};
@@ -85,7 +92,6 @@ macro_rules! nl {
         Rc::new(Doc::Break($txt))
     };
 }
-pub(crate) use nl;
 
 // Macro that creates a Doc::Text
 macro_rules! text {
@@ -123,12 +129,12 @@ pub(crate) use text;
 macro_rules! hardbreak {
     () => {{ Rc::new(Doc::HardBreak) }};
 }
-pub(crate) use hardbreak;
 
 impl Code for Token<'_> {
-    fn to_docs(&self, _: &impl FormattingConfig, _: &mut usize) -> Rc<Doc> {
+    fn to_docs(&self, config: &impl FormattingConfig, _: &mut usize) -> Rc<Doc> {
         match self {
-            Token::Symbol(s) | Token::Literal(s) => text!(*s),
+            Token::Symbol(s) => text!(*s),
+            Token::Literal(s) => text!(&normalise_quotes(s, config.quote_style())),
             Token::Semicolon => text!(";"),
             Token::Newline => text!("\n"),
             Token::LParen => text!("("),
@@ -396,8 +402,144 @@ where
     res
 }
 
+/// Returns the docs of the statements of a block.
+///
+/// The semicolons separating the statements are dropped. If the config
+/// allows inline statements, the statements separated by semicolons
+/// on a single line are kept in one group. Every blank line becomes
+/// an empty doc, so the blank lines are capped only once the docs
+/// are printed.
+///
+/// `is_braced_block` tells whether the statements are inside braces,
+/// where R allows `else` to start a new line.
+fn statements_to_docs(
+    statements: &[Expression<'_>],
+    config: &impl FormattingConfig,
+    doc_ref: &mut usize,
+    is_braced_block: bool,
+) -> Vec<Rc<Doc>> {
+    let mut docs = vec![];
+    let mut line: Vec<Rc<Doc>> = vec![];
+    let mut is_line_open = false;
+    let flush_line = |line: &mut Vec<Rc<Doc>>, docs: &mut Vec<Rc<Doc>>, doc_ref: &mut usize| {
+        let mut line_docs = line.drain(..);
+        if let Some(first) = line_docs.next() {
+            let mut line_doc = first;
+            let mut is_grouped = false;
+            for next in line_docs {
+                line_doc = line_doc.cons(nl!("; ")).cons(next);
+                is_grouped = true;
+            }
+            if is_grouped {
+                line_doc = line_doc.to_group(ShouldBreak::No, doc_ref);
+            }
+            docs.push(line_doc);
+        }
+    };
+    // The indices of the docs of consecutive assignments
+    let mut assignments: Vec<usize> = vec![];
+    let align_assignments = |assignments: &mut Vec<usize>, docs: &mut Vec<Rc<Doc>>| {
+        if let Some(&first) = assignments.first() {
+            let rows: Vec<_> = assignments
+                .iter()
+                .map(|&row| Rc::clone(&docs[row]))
+                .collect();
+            docs[first] = align_rows(&rows, None).cons(Rc::clone(&docs[first]));
+        }
+        assignments.clear();
+    };
+
+    // Whether the previous statement was a function definition
+    // and the number of blank lines after it
+    let mut previous_statement: Option<bool> = None;
+    let mut blank_lines = 0;
+    for statement in statements {
+        match statement {
+            Expression::Whitespace(tokens) => {
+                flush_line(&mut line, &mut docs, doc_ref);
+                let newlines = tokens
+                    .iter()
+                    .filter(|token| matches!(token.token, Token::Newline))
+                    .count();
+                docs.extend(std::iter::repeat_with(|| text!("")).take(newlines));
+                blank_lines += newlines;
+                align_assignments(&mut assignments, &mut docs);
+                is_line_open = false;
+            }
+            Expression::Semicolon(semicolon, _) if semicolon.leading_comments.is_some() => {
+                flush_line(&mut line, &mut docs, doc_ref);
+                align_assignments(&mut assignments, &mut docs);
+                docs.push(semicolon.to_docs(config, doc_ref));
+                is_line_open = false;
+            }
+            Expression::Semicolon(semicolon, newline) => match semicolon.inline_comment {
+                Some(inline_comment) => {
+                    if let Some(last) = line.pop() {
+                        line.push(
+                            last.cons(text!(" "))
+                                .cons(text!(inline_comment, 0, InlineCommentPosition::End))
+                                .cons(hardbreak!()),
+                        );
+                    } else {
+                        docs.push(text!(inline_comment, 0, InlineCommentPosition::End));
+                    }
+                    flush_line(&mut line, &mut docs, doc_ref);
+                    is_line_open = false;
+                }
+                None => {
+                    is_line_open = newline.is_none()
+                        && !line.is_empty()
+                        && config.semicolon_statements() == SemicolonStatements::Inline;
+                }
+            },
+            _ => {
+                let is_function = is_function_definition(statement);
+                if !is_line_open {
+                    flush_line(&mut line, &mut docs, doc_ref);
+                    if previous_statement.is_some_and(|previous| previous || is_function)
+                        && !matches!(statement, Expression::EOF(_))
+                    {
+                        let missing = config
+                            .blank_lines_around_functions()
+                            .saturating_sub(blank_lines);
+                        if missing > 0 {
+                            align_assignments(&mut assignments, &mut docs);
+                        }
+                        docs.extend(std::iter::repeat_with(|| text!("")).take(missing));
+                    }
+                    if config.align_assignments() && assignment_operator(statement).is_some() {
+                        assignments.push(docs.len());
+                    } else {
+                        align_assignments(&mut assignments, &mut docs);
+                    }
+                }
+                previous_statement = Some(is_function);
+                blank_lines = 0;
+                let statement_docs = match statement {
+                    Expression::IfExpression(if_expression) => if_expression_to_docs(
+                        if_expression,
+                        config,
+                        doc_ref,
+                        config.else_on_new_line() && is_braced_block,
+                    ),
+                    _ => statement.to_docs(config, doc_ref),
+                };
+                line.push(statement_docs.to_group(ShouldBreak::No, doc_ref));
+                is_line_open = false;
+            }
+        }
+    }
+    flush_line(&mut line, &mut docs, doc_ref);
+    align_assignments(&mut assignments, &mut docs);
+
+    docs
+}
+
 impl Code for Expression<'_> {
     fn to_docs(&self, config: &impl FormattingConfig, doc_ref: &mut usize) -> Rc<Doc> {
+        if let Some((first, steps)) = pipe_chain(self) {
+            return pipe_chain_to_docs(first, &steps, config, doc_ref);
+        }
         match self {
             Expression::Symbol(token)
             | Expression::Literal(token)
@@ -493,13 +635,7 @@ impl Code for Expression<'_> {
                             .cons(post_delim.to_docs(config, doc_ref))
                             .to_group(ShouldBreak::No, doc_ref)
                     } else {
-                        let docs = term
-                            .iter()
-                            .map(|t| {
-                                t.to_docs(config, doc_ref)
-                                    .to_group(ShouldBreak::No, doc_ref)
-                            })
-                            .collect::<Vec<_>>();
+                        let docs = statements_to_docs(term, config, doc_ref, true);
                         let inner = join_docs(
                             docs,
                             Rc::new(Doc::Nil),
@@ -522,13 +658,7 @@ impl Code for Expression<'_> {
                     term,
                     post_delimiters: None,
                 } => {
-                    let docs = term
-                        .iter()
-                        .map(|t| {
-                            t.to_docs(config, doc_ref)
-                                .to_group(ShouldBreak::No, doc_ref)
-                        })
-                        .collect::<Vec<_>>();
+                    let docs = statements_to_docs(term, config, doc_ref, false);
                     join_docs(
                         docs,
                         Rc::new(Doc::Nil),
@@ -590,31 +720,15 @@ impl Code for Expression<'_> {
                 Token::OldAssign | Token::LAssign | Token::ColonAssign | Token::SuperAssign
                     if !config.allow_nl_after_assignment() =>
                 {
-                    lhs.to_docs(config, doc_ref)
+                    assignment_lhs_to_docs(lhs, config, doc_ref)
                         .cons(nl!(" "))
                         .cons(op.to_docs(config, doc_ref))
                         .cons(nl!(" "))
-                        .cons(rhs.to_docs(config, doc_ref).nest(config.indent()))
+                        .cons(
+                            assigned_value_to_docs(op, rhs, config, doc_ref).nest(config.indent()),
+                        )
                 }
-                Token::RAssign
-                | Token::Equal
-                | Token::NotEqual
-                | Token::LowerThan
-                | Token::GreaterThan
-                | Token::LowerEqual
-                | Token::GreaterEqual
-                | Token::Divide
-                | Token::Multiply
-                | Token::Minus
-                | Token::Plus
-                | Token::And
-                | Token::VectorizedAnd
-                | Token::Or
-                | Token::VectorizedOr
-                | Token::Pipe
-                | Token::Modulo
-                | Token::Tilde
-                | Token::Special(_) => lhs
+                _ if spaces_around_operator(&op.token, config) == Some(true) => lhs
                     .to_docs(config, doc_ref)
                     .cons(text!(" "))
                     .cons(op.to_docs(config, doc_ref))
@@ -624,13 +738,7 @@ impl Code for Expression<'_> {
                             .cons(rhs.to_docs(config, doc_ref))
                             .nest(config.indent()),
                     ),
-                Token::Dollar
-                | Token::NsGet
-                | Token::NsGetInt
-                | Token::Colon
-                | Token::Slot
-                | Token::Power
-                | Token::Help => lhs
+                _ if spaces_around_operator(&op.token, config) == Some(false) => lhs
                     .to_docs(config, doc_ref)
                     .cons(op.to_docs(config, doc_ref))
                     .cons(rhs.to_docs(config, doc_ref).nest(config.indent())),
@@ -640,198 +748,75 @@ impl Code for Expression<'_> {
                     &op
                 ),
             },
-            Expression::Formula(tilde, term) => tilde
+            Expression::Formula(formula) => match &formula.lhs {
+                None => formula
+                    .tilde
+                    .to_docs(config, doc_ref)
+                    .cons(if matches!(*formula.rhs, Expression::Symbol(_)) {
+                        text!("")
+                    } else {
+                        text!(" ")
+                    })
+                    .cons(formula.rhs.to_docs(config, doc_ref)),
+                Some(lhs) => lhs
+                    .to_docs(config, doc_ref)
+                    .cons(text!(" "))
+                    .cons(formula.tilde.to_docs(config, doc_ref))
+                    .to_group(ShouldBreak::No, doc_ref)
+                    .cons(
+                        nl!(" ")
+                            .cons(
+                                formula
+                                    .rhs
+                                    .to_docs(config, doc_ref)
+                                    .to_group(ShouldBreak::No, doc_ref),
+                            )
+                            .nest(config.indent()),
+                    )
+                    .to_group(ShouldBreak::No, doc_ref),
+            },
+            Expression::NamespaceAccess(namespace_access) => namespace_access
+                .namespace
                 .to_docs(config, doc_ref)
-                .cons(if matches!(**term, Expression::Symbol(_)) {
-                    text!("")
-                } else {
-                    text!(" ")
-                })
-                .cons(term.to_docs(config, doc_ref)),
+                .cons(namespace_access.operator.to_docs(config, doc_ref))
+                .cons(
+                    namespace_access
+                        .name
+                        .to_docs(config, doc_ref)
+                        .to_group(ShouldBreak::No, doc_ref),
+                )
+                .to_group(ShouldBreak::No, doc_ref),
+            Expression::MemberAccess(member_access) => member_access
+                .object
+                .to_docs(config, doc_ref)
+                .cons(member_access.operator.to_docs(config, doc_ref))
+                .cons(
+                    member_access
+                        .member
+                        .to_docs(config, doc_ref)
+                        .to_group(ShouldBreak::No, doc_ref),
+                )
+                .to_group(ShouldBreak::No, doc_ref),
+            Expression::SlotAccess(slot_access) => slot_access
+                .object
+                .to_docs(config, doc_ref)
+                .cons(slot_access.operator.to_docs(config, doc_ref))
+                .cons(
+                    slot_access
+                        .slot
+                        .to_docs(config, doc_ref)
+                        .to_group(ShouldBreak::No, doc_ref),
+                )
+                .to_group(ShouldBreak::No, doc_ref),
             Expression::Newline(_) => Rc::new(Doc::Break("\n")),
+            Expression::Semicolon(semicolon, _) => semicolon.to_docs(config, doc_ref),
             Expression::EOF(eof) => eof.to_docs(config, doc_ref),
             Expression::Whitespace(_) => text!(""),
             Expression::FunctionDef(function_def) => {
-                let (keyword, args, body) = (
-                    function_def.keyword,
-                    &function_def.arguments,
-                    &function_def.body,
-                );
-
-                let after_right_delim_doc = if args.right_delimeter.is_inline_commented() {
-                    nl!(" ")
-                } else {
-                    text!(" ")
-                };
-
-                match config.function_line_breaks() {
-                    FunctionLineBreaks::Hanging => {
-                        let args_doc = join_docs_ungroupped(
-                            args.args.iter().map(|arg| {
-                                arg.to_docs(config, doc_ref)
-                                    .to_group(ShouldBreak::No, doc_ref)
-                            }),
-                            Rc::new(Doc::Nil),
-                            config,
-                        );
-                        let args_group = args
-                            .left_delimeter
-                            .to_docs(config, doc_ref)
-                            .cons(args_doc.nest_hanging())
-                            .cons(
-                                args.right_delimeter
-                                    .to_docs(config, doc_ref)
-                                    .cons(after_right_delim_doc)
-                                    .to_group(ShouldBreak::No, doc_ref),
-                            );
-                        keyword
-                            .to_docs(config, doc_ref)
-                            .cons(args_group.to_group(ShouldBreak::No, doc_ref))
-                            .cons(
-                                body.to_docs(config, doc_ref)
-                                    .to_group(ShouldBreak::No, doc_ref),
-                            )
-                            .to_group(ShouldBreak::No, doc_ref)
-                    }
-                    FunctionLineBreaks::Double => {
-                        let args_doc = join_docs_ungroupped(
-                            args.args.iter().map(|arg| {
-                                arg.to_docs(config, doc_ref)
-                                    .to_group(ShouldBreak::No, doc_ref)
-                            }),
-                            Rc::new(Doc::Nil),
-                            config,
-                        );
-                        let args_group = args
-                            .left_delimeter
-                            .to_docs(config, doc_ref)
-                            .cons(nl!(""))
-                            .cons(args_doc)
-                            .nest(2 * config.indent())
-                            .cons(nl!(""))
-                            .cons(
-                                args.right_delimeter
-                                    .to_docs(config, doc_ref)
-                                    .cons(after_right_delim_doc)
-                                    .to_group(ShouldBreak::No, doc_ref),
-                            )
-                            .to_group(ShouldBreak::No, doc_ref);
-                        keyword
-                            .to_docs(config, doc_ref)
-                            .cons(args_group)
-                            .cons(body.to_docs(config, doc_ref))
-                            .to_group(ShouldBreak::No, doc_ref)
-                    }
-                    FunctionLineBreaks::Single => {
-                        let args_doc = join_docs_ungroupped(
-                            args.args.iter().map(|arg| {
-                                arg.to_docs(config, doc_ref)
-                                    .to_group(ShouldBreak::No, doc_ref)
-                            }),
-                            Rc::new(Doc::Nil),
-                            config,
-                        );
-                        let args_group = args
-                            .left_delimeter
-                            .to_docs(config, doc_ref)
-                            .cons(nl!(""))
-                            .cons(args_doc)
-                            .nest(config.indent())
-                            .cons(nl!(""))
-                            .cons(
-                                args.right_delimeter
-                                    .to_docs(config, doc_ref)
-                                    .cons(after_right_delim_doc)
-                                    .to_group(ShouldBreak::No, doc_ref),
-                            )
-                            .to_group(ShouldBreak::No, doc_ref);
-                        keyword
-                            .to_docs(config, doc_ref)
-                            .cons(args_group)
-                            .cons(body.to_docs(config, doc_ref))
-                            .to_group(ShouldBreak::No, doc_ref)
-                    }
-                }
+                function_def_to_docs(function_def, config, doc_ref, false)
             }
             Expression::IfExpression(if_expression) => {
-                let (if_conditional, else_ifs, trailing_else) = (
-                    &if_expression.if_conditional,
-                    &if_expression.else_ifs,
-                    &if_expression.trailing_else,
-                );
-
-                let if_conditional_to_docs =
-                    |if_conditional: &IfConditional<'_>, doc_ref: &mut usize| {
-                        let (keyword, left_delim, condition, right_delim, body) = (
-                            if_conditional.keyword,
-                            if_conditional.left_delimiter,
-                            &if_conditional.condition,
-                            if_conditional.right_delimiter,
-                            &if_conditional.body,
-                        );
-                        let condition_docs = left_delim
-                            .to_docs(config, doc_ref)
-                            .cons(nl!(""))
-                            .cons(condition.to_docs(config, doc_ref))
-                            .nest(config.indent())
-                            .cons(nl!(""))
-                            .to_group(ShouldBreak::No, doc_ref);
-                        keyword
-                            .to_docs(config, doc_ref)
-                            .cons(text!(" "))
-                            .cons(condition_docs)
-                            .cons(
-                                right_delim
-                                    .to_docs(config, doc_ref)
-                                    .cons(nl!(" "))
-                                    .to_group(ShouldBreak::No, doc_ref),
-                            )
-                            .cons(
-                                body.to_docs(config, doc_ref)
-                                    .to_group(ShouldBreak::No, doc_ref),
-                            )
-                    };
-                let mut docs = if_conditional_to_docs(if_conditional, doc_ref);
-                for else_if in else_ifs {
-                    let (else_keyword, conditional) =
-                        (else_if.else_keyword, &else_if.if_conditional);
-                    docs = docs
-                        .cons(text!(" "))
-                        .cons(
-                            else_keyword
-                                .to_docs(config, doc_ref)
-                                .cons(if else_keyword.inline_comment.is_some() {
-                                    nl!(" ")
-                                } else {
-                                    text!(" ")
-                                })
-                                .to_group(ShouldBreak::No, doc_ref),
-                        )
-                        .cons(
-                            if_conditional_to_docs(conditional, doc_ref)
-                                .to_group(ShouldBreak::No, doc_ref),
-                        );
-                }
-                if let Some(trailing_else) = trailing_else {
-                    let (else_keyword, body) = (&trailing_else.else_keyword, &trailing_else.body);
-                    docs = docs
-                        .cons(text!(" "))
-                        .cons(
-                            else_keyword
-                                .to_docs(config, doc_ref)
-                                .cons(if else_keyword.inline_comment.is_some() {
-                                    nl!(" ")
-                                } else {
-                                    text!(" ")
-                                })
-                                .to_group(ShouldBreak::No, doc_ref),
-                        )
-                        .cons(
-                            body.to_docs(config, doc_ref)
-                                .to_group(ShouldBreak::No, doc_ref),
-                        );
-                }
-                docs
+                if_expression_to_docs(if_expression, config, doc_ref, false)
             }
             Expression::WhileExpression(while_expression) => {
                 let (keyword, condition, body) = (
@@ -839,11 +824,12 @@ impl Code for Expression<'_> {
                     &while_expression.condition,
                     &while_expression.body,
                 );
+                let is_allman = config.brace_style() == BraceStyle::Allman;
                 keyword
                     .to_docs(config, doc_ref)
                     .cons(text!(" "))
                     .cons(condition.to_docs(config, doc_ref))
-                    .cons(text!(" "))
+                    .cons(body_separator(body, text!(" "), is_allman, doc_ref))
                     .cons(body.to_docs(config, doc_ref))
                     .to_group(ShouldBreak::No, doc_ref)
             }
@@ -856,9 +842,10 @@ impl Code for Expression<'_> {
                     false
                 };
                 if is_body_lbraced {
+                    let is_allman = config.brace_style() == BraceStyle::Allman;
                     keyword
                         .to_docs(config, doc_ref)
-                        .cons(text!(" "))
+                        .cons(body_separator(body, text!(" "), is_allman, doc_ref))
                         .cons(body.to_docs(config, doc_ref))
                         .to_group(ShouldBreak::No, doc_ref)
                 } else {
@@ -971,7 +958,8 @@ impl Code for Expression<'_> {
                     // The below needs to be nl!(" ") in case
                     // the body is not a bracketed expression
                     .cons(if is_body_bracketed_expression {
-                        text!(" ")
+                        let is_allman = config.brace_style() == BraceStyle::Allman;
+                        body_separator(body, text!(" "), is_allman, doc_ref)
                     } else {
                         nl!(" ")
                     })
@@ -1030,29 +1018,9 @@ impl Code for Expression<'_> {
                                     .cons(acc_rhs.nest(config.indent()));
                                 last_op = Some(op);
                             }
-                            Token::OldAssign
-                            | Token::LAssign
-                            | Token::ColonAssign
-                            | Token::SuperAssign
-                            | Token::RAssign
-                            | Token::Equal
-                            | Token::NotEqual
-                            | Token::LowerThan
-                            | Token::GreaterThan
-                            | Token::LowerEqual
-                            | Token::GreaterEqual
-                            | Token::Divide
-                            | Token::Multiply
-                            | Token::Minus
-                            | Token::Plus
-                            | Token::And
-                            | Token::VectorizedAnd
-                            | Token::Or
-                            | Token::VectorizedOr
-                            | Token::Pipe
-                            | Token::Modulo
-                            | Token::Tilde
-                            | Token::Special(_) => {
+                            _ if spaces_around_operator(&last_op_token.token, config)
+                                == Some(true) =>
+                            {
                                 acc_rhs = rhs
                                     .to_docs(config, doc_ref)
                                     .cons(text!(" "))
@@ -1062,13 +1030,9 @@ impl Code for Expression<'_> {
                                     .cons(acc_rhs);
                                 last_op = Some(op);
                             }
-                            Token::Dollar
-                            | Token::NsGet
-                            | Token::NsGetInt
-                            | Token::Colon
-                            | Token::Slot
-                            | Token::Power
-                            | Token::Help => {
+                            _ if spaces_around_operator(&last_op_token.token, config)
+                                == Some(false) =>
+                            {
                                 acc_rhs = rhs
                                     .to_docs(config, doc_ref)
                                     .cons(last_op_token.to_docs(config, doc_ref))
@@ -1083,13 +1047,19 @@ impl Code for Expression<'_> {
                         },
                         None => {
                             last_op = Some(op);
-                            acc_rhs = rhs
-                                .to_docs(config, doc_ref)
+                            acc_rhs = assigned_value_to_docs(op, rhs, config, doc_ref)
                                 .to_group(ShouldBreak::No, doc_ref);
                         }
                     }
                 }
                 if let Some(last_op) = last_op {
+                    let lhs_docs = match last_op.token {
+                        Token::OldAssign
+                        | Token::LAssign
+                        | Token::ColonAssign
+                        | Token::SuperAssign => assignment_lhs_to_docs(lhs, config, doc_ref),
+                        _ => lhs.to_docs(config, doc_ref),
+                    };
                     match last_op.token {
                         Token::OldAssign
                         | Token::LAssign
@@ -1098,7 +1068,7 @@ impl Code for Expression<'_> {
                             if !config.allow_nl_after_assignment()
                                 && last_op.inline_comment.is_none() =>
                         {
-                            lhs.to_docs(config, doc_ref)
+                            lhs_docs
                                 .cons(text!(" "))
                                 .cons(last_op.to_docs(config, doc_ref))
                                 .cons(text!(" "))
@@ -1112,53 +1082,27 @@ impl Code for Expression<'_> {
                             if !config.allow_nl_after_assignment()
                                 && last_op.inline_comment.is_some() =>
                         {
-                            lhs.to_docs(config, doc_ref)
+                            lhs_docs
                                 .cons(text!(" "))
                                 .cons(last_op.to_docs(config, doc_ref))
                                 .cons(nl!(" "))
                                 .cons(acc_rhs.nest(config.indent()))
                                 .to_group(ShouldBreak::No, doc_ref)
                         }
-                        Token::OldAssign
-                        | Token::LAssign
-                        | Token::ColonAssign
-                        | Token::SuperAssign
-                        | Token::RAssign
-                        | Token::Equal
-                        | Token::NotEqual
-                        | Token::LowerThan
-                        | Token::GreaterThan
-                        | Token::LowerEqual
-                        | Token::GreaterEqual
-                        | Token::Divide
-                        | Token::Multiply
-                        | Token::Minus
-                        | Token::Plus
-                        | Token::And
-                        | Token::VectorizedAnd
-                        | Token::Or
-                        | Token::VectorizedOr
-                        | Token::Pipe
-                        | Token::Modulo
-                        | Token::Tilde
-                        | Token::Special(_) => lhs
-                            .to_docs(config, doc_ref)
-                            .cons(text!(" "))
-                            .cons(last_op.to_docs(config, doc_ref))
-                            .to_group(ShouldBreak::No, doc_ref)
-                            .cons(nl!(" ").cons(acc_rhs).nest(config.indent()))
-                            .to_group(ShouldBreak::No, doc_ref),
-                        Token::Dollar
-                        | Token::NsGet
-                        | Token::NsGetInt
-                        | Token::Colon
-                        | Token::Slot
-                        | Token::Power
-                        | Token::Help => lhs
-                            .to_docs(config, doc_ref)
-                            .cons(last_op.to_docs(config, doc_ref))
-                            .cons(acc_rhs)
-                            .to_group(ShouldBreak::No, doc_ref),
+                        _ if spaces_around_operator(&last_op.token, config) == Some(true) => {
+                            lhs_docs
+                                .cons(text!(" "))
+                                .cons(last_op.to_docs(config, doc_ref))
+                                .to_group(ShouldBreak::No, doc_ref)
+                                .cons(nl!(" ").cons(acc_rhs).nest(config.indent()))
+                                .to_group(ShouldBreak::No, doc_ref)
+                        }
+                        _ if spaces_around_operator(&last_op.token, config) == Some(false) => {
+                            lhs_docs
+                                .cons(last_op.to_docs(config, doc_ref))
+                                .cons(acc_rhs)
+                                .to_group(ShouldBreak::No, doc_ref)
+                        }
                         _ => panic!(
                             "Got a not a binary operator token inside a binary expression when \
                      formatting. Token: {:?}",
@@ -1217,14 +1161,50 @@ impl Code for Args<'_> {
                         .to_docs(config, doc_ref)
                         .to_group(ShouldBreak::No, doc_ref),
                 });
-                let inside_delims = other_args
+                let mut arg_docs: Vec<_> = other_args.into_iter().chain(last_arg).collect();
+                if config.align_named_arguments() {
+                    // Aligns the runs of consecutive named arguments
+                    // if the group of the arguments breaks
+                    let is_named = |arg: &Arg| {
+                        matches!(arg, Arg::Proper(Some(expression), _)
+                            if assignment_operator(expression) == Some(&Token::OldAssign))
+                    };
+                    let mut start = 0;
+                    while start < self.args.len() {
+                        let end = start
+                            + self.args[start..]
+                                .iter()
+                                .take_while(|arg| is_named(arg))
+                                .count();
+                        if end > start {
+                            let block = align_rows(&arg_docs[start..end], Some(observed_doc + 1));
+                            arg_docs[start] = block.cons(Rc::clone(&arg_docs[start]));
+                        }
+                        start = end + 1;
+                    }
+                }
+                let inside_delims = arg_docs
                 .into_iter()
-                .chain(last_arg)
                 .reduce(|first, second| first.cons(nl!(" ")).cons(second))
                 .expect(
                     "There is at least last_arg doc, otherwise we should be in the None match arm",
-                )
-                .to_group(ShouldBreak::No, &mut observed_doc);
+                );
+                if config.call_arguments_layout() == CallArgumentsLayout::OnePerLine {
+                    // The arguments share the group with the delimiters,
+                    // so every argument goes on its own line once the call breaks
+                    let call = self
+                        .left_delimeter
+                        .to_docs(config, doc_ref)
+                        .cons(nl!("").cons(inside_delims).nest(config.indent()))
+                        .cons(nl!(""))
+                        .cons(right_delim)
+                        .to_group(ShouldBreak::No, &mut observed_doc);
+                    return match inline_comment {
+                        Some(inline) => call.cons(text!(" ")).cons(inline),
+                        None => call,
+                    };
+                }
+                let inside_delims = inside_delims.to_group(ShouldBreak::No, &mut observed_doc);
                 if let Some(inline) = inline_comment {
                     self.left_delimeter
                         .to_docs(config, doc_ref)
@@ -1278,11 +1258,36 @@ impl Code for Arg<'_> {
     fn to_docs(&self, config: &impl FormattingConfig, doc_ref: &mut usize) -> Rc<Doc> {
         match self {
             Arg::Proper(expr, comma) => {
+                let expr = match expr {
+                    Some(Expression::MultiBop(name, other)) => match other.as_slice() {
+                        // The functions passed as named arguments keep their brace
+                        // on the line of the name
+                        [(op, value)]
+                            if op.token == Token::OldAssign
+                                && op.inline_comment.is_none()
+                                && !config.allow_nl_after_assignment() =>
+                        {
+                            let value = match &**value {
+                                Expression::FunctionDef(function_def) => {
+                                    function_def_to_docs(function_def, config, doc_ref, false)
+                                }
+                                value => value.to_docs(config, doc_ref),
+                            };
+                            assignment_lhs_to_docs(name, config, doc_ref)
+                                .cons(text!(" "))
+                                .cons(op.to_docs(config, doc_ref))
+                                .cons(text!(" "))
+                                .cons(value.to_group(ShouldBreak::No, doc_ref))
+                                .to_group(ShouldBreak::No, doc_ref)
+                        }
+                        _ => expr.to_docs(config, doc_ref),
+                    },
+                    _ => expr.to_docs(config, doc_ref),
+                };
                 if let Some(comma) = comma {
-                    expr.to_docs(config, doc_ref)
-                        .cons(comma.to_docs(config, doc_ref))
+                    expr.cons(comma.to_docs(config, doc_ref))
                 } else {
-                    expr.to_docs(config, doc_ref)
+                    expr
                 }
             }
             Arg::EmptyEqual(arg_name, equal_sign, comma) => arg_name
@@ -1295,6 +1300,368 @@ impl Code for Arg<'_> {
     }
 }
 
+/// Returns the docs of a function definition.
+///
+/// If `brace_on_new_line` is set, a braced body starts on a new line.
+fn function_def_to_docs(
+    function_def: &FunctionDefinition<'_>,
+    config: &impl FormattingConfig,
+    doc_ref: &mut usize,
+    brace_on_new_line: bool,
+) -> Rc<Doc> {
+    let (keyword, args, body) = (
+        function_def.keyword,
+        &function_def.arguments,
+        &function_def.body,
+    );
+
+    let after_right_delim_doc = if args.right_delimeter.is_inline_commented() {
+        nl!(" ")
+    } else {
+        text!(" ")
+    };
+    let after_right_delim_doc =
+        body_separator(body, after_right_delim_doc, brace_on_new_line, doc_ref);
+
+    match config.function_line_breaks() {
+        FunctionLineBreaks::Hanging => {
+            let args_doc = join_docs_ungroupped(
+                args.args.iter().map(|arg| {
+                    arg.to_docs(config, doc_ref)
+                        .to_group(ShouldBreak::No, doc_ref)
+                }),
+                Rc::new(Doc::Nil),
+                config,
+            );
+            let args_group = args
+                .left_delimeter
+                .to_docs(config, doc_ref)
+                .cons(args_doc.nest_hanging())
+                .cons(
+                    args.right_delimeter
+                        .to_docs(config, doc_ref)
+                        .cons(after_right_delim_doc)
+                        .to_group(ShouldBreak::No, doc_ref),
+                );
+            keyword
+                .to_docs(config, doc_ref)
+                .cons(args_group.to_group(ShouldBreak::No, doc_ref))
+                .cons(
+                    body.to_docs(config, doc_ref)
+                        .to_group(ShouldBreak::No, doc_ref),
+                )
+                .to_group(ShouldBreak::No, doc_ref)
+        }
+        FunctionLineBreaks::Double => {
+            let args_doc = join_docs_ungroupped(
+                args.args.iter().map(|arg| {
+                    arg.to_docs(config, doc_ref)
+                        .to_group(ShouldBreak::No, doc_ref)
+                }),
+                Rc::new(Doc::Nil),
+                config,
+            );
+            let args_group = args
+                .left_delimeter
+                .to_docs(config, doc_ref)
+                .cons(nl!(""))
+                .cons(args_doc)
+                .nest(2 * config.indent())
+                .cons(nl!(""))
+                .cons(
+                    args.right_delimeter
+                        .to_docs(config, doc_ref)
+                        .cons(after_right_delim_doc)
+                        .to_group(ShouldBreak::No, doc_ref),
+                )
+                .to_group(ShouldBreak::No, doc_ref);
+            keyword
+                .to_docs(config, doc_ref)
+                .cons(args_group)
+                .cons(body.to_docs(config, doc_ref))
+                .to_group(ShouldBreak::No, doc_ref)
+        }
+        FunctionLineBreaks::Single => {
+            let args_doc = join_docs_ungroupped(
+                args.args.iter().map(|arg| {
+                    arg.to_docs(config, doc_ref)
+                        .to_group(ShouldBreak::No, doc_ref)
+                }),
+                Rc::new(Doc::Nil),
+                config,
+            );
+            let args_group = args
+                .left_delimeter
+                .to_docs(config, doc_ref)
+                .cons(nl!(""))
+                .cons(args_doc)
+                .nest(config.indent())
+                .cons(nl!(""))
+                .cons(
+                    args.right_delimeter
+                        .to_docs(config, doc_ref)
+                        .cons(after_right_delim_doc)
+                        .to_group(ShouldBreak::No, doc_ref),
+                )
+                .to_group(ShouldBreak::No, doc_ref);
+            keyword
+                .to_docs(config, doc_ref)
+                .cons(args_group)
+                .cons(body.to_docs(config, doc_ref))
+                .to_group(ShouldBreak::No, doc_ref)
+        }
+    }
+}
+
+/// Returns the docs of an if expression.
+///
+/// If `else_on_new_line` is set, the else keywords start new lines.
+/// This is only valid R inside braces, because outside of them
+/// the if expression ends at the end of the line.
+fn if_expression_to_docs(
+    if_expression: &IfExpression<'_>,
+    config: &impl FormattingConfig,
+    doc_ref: &mut usize,
+    else_on_new_line: bool,
+) -> Rc<Doc> {
+    let (if_conditional, else_ifs, trailing_else) = (
+        &if_expression.if_conditional,
+        &if_expression.else_ifs,
+        &if_expression.trailing_else,
+    );
+
+    let is_allman = config.brace_style() == BraceStyle::Allman;
+    let else_separator = |doc_ref: &mut usize| {
+        if else_on_new_line {
+            nl!(" ").to_group(ShouldBreak::Yes, doc_ref)
+        } else {
+            text!(" ")
+        }
+    };
+    let if_conditional_to_docs = |if_conditional: &IfConditional<'_>, doc_ref: &mut usize| {
+        let (keyword, left_delim, condition, right_delim, body) = (
+            if_conditional.keyword,
+            if_conditional.left_delimiter,
+            &if_conditional.condition,
+            if_conditional.right_delimiter,
+            &if_conditional.body,
+        );
+        let condition_docs = left_delim
+            .to_docs(config, doc_ref)
+            .cons(nl!(""))
+            .cons(condition.to_docs(config, doc_ref))
+            .nest(config.indent())
+            .cons(nl!(""))
+            .to_group(ShouldBreak::No, doc_ref);
+        let separator = body_separator(body, nl!(" "), is_allman, doc_ref);
+        keyword
+            .to_docs(config, doc_ref)
+            .cons(text!(" "))
+            .cons(condition_docs)
+            .cons(
+                right_delim
+                    .to_docs(config, doc_ref)
+                    .cons(separator)
+                    .to_group(ShouldBreak::No, doc_ref),
+            )
+            .cons(
+                body.to_docs(config, doc_ref)
+                    .to_group(ShouldBreak::No, doc_ref),
+            )
+    };
+    let mut docs = if_conditional_to_docs(if_conditional, doc_ref);
+    for else_if in else_ifs {
+        let (else_keyword, conditional) = (else_if.else_keyword, &else_if.if_conditional);
+        docs = docs
+            .cons(else_separator(doc_ref))
+            .cons(
+                else_keyword
+                    .to_docs(config, doc_ref)
+                    .cons(if else_keyword.inline_comment.is_some() {
+                        nl!(" ")
+                    } else {
+                        text!(" ")
+                    })
+                    .to_group(ShouldBreak::No, doc_ref),
+            )
+            .cons(if_conditional_to_docs(conditional, doc_ref).to_group(ShouldBreak::No, doc_ref));
+    }
+    if let Some(trailing_else) = trailing_else {
+        let (else_keyword, body) = (&trailing_else.else_keyword, &trailing_else.body);
+        let separator = if else_keyword.inline_comment.is_some() {
+            nl!(" ")
+        } else {
+            text!(" ")
+        };
+        docs = docs
+            .cons(else_separator(doc_ref))
+            .cons(
+                else_keyword
+                    .to_docs(config, doc_ref)
+                    .cons(body_separator(body, separator, is_allman, doc_ref))
+                    .to_group(ShouldBreak::No, doc_ref),
+            )
+            .cons(
+                body.to_docs(config, doc_ref)
+                    .to_group(ShouldBreak::No, doc_ref),
+            );
+    }
+    docs
+}
+
+/// Returns the docs of a pipe chain.
+///
+/// The first element stays on the line of the chain and the steps
+/// hang below it. The chain breaks after every pipe or after none,
+/// and always breaks if it has more steps than the config allows
+/// on one line.
+fn pipe_chain_to_docs(
+    first: &Expression<'_>,
+    steps: &PipeSteps<'_, '_>,
+    config: &impl FormattingConfig,
+    doc_ref: &mut usize,
+) -> Rc<Doc> {
+    let should_break = match config.max_pipe_steps_on_one_line() {
+        Some(max_steps) if steps.len() > max_steps => ShouldBreak::Yes,
+        _ => ShouldBreak::No,
+    };
+    let with_pipe = |docs: Rc<Doc>, pipe: &CommentedToken<'_>, doc_ref: &mut usize| {
+        let docs = if spaces_around_operator(&pipe.token, config) == Some(false) {
+            docs
+        } else {
+            docs.cons(text!(" "))
+        };
+        docs.cons(pipe.to_docs(config, doc_ref))
+            .to_group(ShouldBreak::No, doc_ref)
+    };
+    let line_break = |pipe: &CommentedToken<'_>| {
+        if spaces_around_operator(&pipe.token, config) == Some(false) {
+            nl!("")
+        } else {
+            nl!(" ")
+        }
+    };
+
+    let first_docs = with_pipe(first.to_docs(config, doc_ref), steps[0].0, doc_ref);
+    let mut steps_docs = Rc::new(Doc::Nil);
+    for (index, (pipe, step)) in steps.iter().enumerate() {
+        let step_docs = match steps.get(index + 1) {
+            Some((next_pipe, _)) => with_pipe(step.to_docs(config, doc_ref), next_pipe, doc_ref),
+            None => step
+                .to_docs(config, doc_ref)
+                .to_group(ShouldBreak::No, doc_ref),
+        };
+        steps_docs = steps_docs.cons(line_break(pipe)).cons(step_docs);
+    }
+    first_docs
+        .cons(steps_docs.nest(config.indent()))
+        .to_group(should_break, doc_ref)
+}
+
+/// Returns the separator between a header, such as `if (x)`, and its body.
+///
+/// If `brace_on_new_line` is set, a body in braces starts on a new line.
+/// Otherwise, the separator is `separator`.
+fn body_separator(
+    body: &Expression<'_>,
+    separator: Rc<Doc>,
+    brace_on_new_line: bool,
+    doc_ref: &mut usize,
+) -> Rc<Doc> {
+    let is_braced = matches!(body, Expression::Term(term)
+        if term.pre_delimiters.is_some_and(|delim| matches!(delim.token, Token::LBrace))
+            && !is_term_embracing_op(term));
+    if brace_on_new_line && is_braced {
+        nl!(" ").to_group(ShouldBreak::Yes, doc_ref)
+    } else {
+        separator
+    }
+}
+
+/// Returns the docs of the value of an assignment.
+///
+/// The functions assigned with `<-`, `<<-` or `=` start their body
+/// on a new line if the config says so.
+fn assigned_value_to_docs(
+    op: &CommentedToken<'_>,
+    value: &Expression<'_>,
+    config: &impl FormattingConfig,
+    doc_ref: &mut usize,
+) -> Rc<Doc> {
+    match value {
+        Expression::FunctionDef(function_def)
+            if matches!(
+                op.token,
+                Token::LAssign | Token::SuperAssign | Token::OldAssign
+            ) =>
+        {
+            function_def_to_docs(
+                function_def,
+                config,
+                doc_ref,
+                config.function_brace_on_new_line(),
+            )
+        }
+        _ => value.to_docs(config, doc_ref),
+    }
+}
+
+/// Returns the docs of the left-hand side of an assignment.
+///
+/// If assignments or named arguments are aligned, the left-hand side
+/// is a cell padded to the width of the widest cell of its block.
+fn assignment_lhs_to_docs(
+    lhs: &Expression<'_>,
+    config: &impl FormattingConfig,
+    doc_ref: &mut usize,
+) -> Rc<Doc> {
+    let lhs = lhs.to_docs(config, doc_ref);
+    if config.align_assignments() || config.align_named_arguments() {
+        lhs.to_align_cell(doc_ref)
+    } else {
+        lhs
+    }
+}
+
+/// Returns the operator of an assignment, e.g. `<-` in `x <- 1`.
+fn assignment_operator<'a>(expression: &Expression<'a>) -> Option<&'a Token<'a>> {
+    let op = match expression {
+        Expression::Bop(op, _, _) => *op,
+        Expression::MultiBop(_, other) => other.first()?.0,
+        _ => return None,
+    };
+    match op.token {
+        Token::OldAssign | Token::LAssign | Token::ColonAssign | Token::SuperAssign => {
+            Some(&op.token)
+        }
+        _ => None,
+    }
+}
+
+/// Whether the statement is a function definition or an assignment
+/// of one, e.g. `f <- function() NULL`.
+fn is_function_definition(statement: &Expression) -> bool {
+    match statement {
+        Expression::FunctionDef(_) => true,
+        Expression::Bop(op, lhs, rhs) => match op.token {
+            Token::LAssign | Token::SuperAssign | Token::OldAssign => is_function_definition(rhs),
+            Token::RAssign => is_function_definition(lhs),
+            _ => false,
+        },
+        Expression::MultiBop(lhs, other) => match other.last() {
+            Some((op, rhs)) => match op.token {
+                Token::LAssign | Token::SuperAssign | Token::OldAssign => {
+                    is_function_definition(rhs)
+                }
+                Token::RAssign => is_function_definition(lhs),
+                _ => false,
+            },
+            None => is_function_definition(lhs),
+        },
+        _ => false,
+    }
+}
+
 fn is_expression_bracketed_term_or_function_def(expr: &Option<&Expression>) -> bool {
     expr.as_ref().is_some_and(|expr| match expr {
         Expression::Term(term) => {
@@ -1351,6 +1718,8 @@ fn has_forced_line_breaks(doc: &Rc<Doc>, inside_a_group_with_should_break: bool)
                 || matches!(group_props.1, ShouldBreak::Propagate),
         ),
         Doc::HardBreak => true,
+        Doc::Align(inner, _) => has_forced_line_breaks(inner, inside_a_group_with_should_break),
+        Doc::AlignBlock(_, _) => false,
     }
 }
 
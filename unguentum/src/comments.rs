@@ -0,0 +1,260 @@
+use std::{borrow::Cow, rc::Rc};
+
+use crate::{config::FormattingConfig, format::SimpleDoc};
+
+/// Inserts a space after the `#` of a comment.
+///
+/// The roxygen comments (`#'`), shebangs and other directives (`#!`),
+/// the printed output (`#>`) and the comments starting with `##`
+/// stay as they are.
+pub(crate) fn normalise_comment(comment: &str) -> Cow<'_, str> {
+    match comment.strip_prefix('#') {
+        Some(content)
+            if !content.is_empty()
+                && !content.starts_with(|c: char| c.is_whitespace() || "#'!>".contains(c)) =>
+        {
+            Cow::Owned(format!("# {content}"))
+        }
+        _ => Cow::Borrowed(comment),
+    }
+}
+
+/// Returns whether the comment is a line of prose that can be reflowed.
+///
+/// The commented-out code, lists, directives, roxygen and other special
+/// comments are never reflowed. Code is told apart from prose by the
+/// presence of the tokens that rarely appear in prose.
+fn is_prose(comment: &str) -> bool {
+    const CODE_MARKERS: [&str; 16] = [
+        "<-", "->", "|>", "%>%", "{", "}", "==", "!=", "::", "$", ";", "()", "`", "\"", " = ", "[",
+    ];
+    let Some(content) = comment.strip_prefix("# ") else {
+        return false;
+    };
+    let is_list_item = content.starts_with(['-', '*', '+', '|', '>'])
+        || content
+            .split_once(". ")
+            .is_some_and(|(number, _)| number.chars().all(|c| c.is_ascii_digit()));
+    let is_identifier_char = |c: char| c.is_alphanumeric() || c == '_' || c == '.';
+    // A parenthesis right after an identifier, as in `f(x)`, or right before one
+    let is_call = content
+        .match_indices(['(', ')'])
+        .any(|(index, parenthesis)| {
+            let before = content[..index].chars().next_back();
+            let after = content[index + 1..].chars().next();
+            match parenthesis {
+                "(" => before.is_some_and(is_identifier_char),
+                _ => after.is_some_and(|c| is_identifier_char(c) || c == '('),
+            }
+        });
+    !content.is_empty()
+        && !content.starts_with(char::is_whitespace)
+        && !is_list_item
+        && !is_call
+        && !is_directive(content)
+        && !content.ends_with([
+            '(', ',', '+', '-', '*', '/', '^', '=', '<', '>', '&', '|', '~', '!', '%',
+        ])
+        && !CODE_MARKERS.iter().any(|marker| content.contains(marker))
+}
+
+/// Returns whether the content of a comment is a directive to a tool
+/// or a note to developers, such as `nolint start` or `TODO: ...`.
+fn is_directive(content: &str) -> bool {
+    const DIRECTIVES: [&str; 5] = ["nolint", "nocov", "styler:", "fmt:", "tergo:"];
+    const NOTES: [&str; 5] = ["TODO", "FIXME", "XXX", "HACK", "NOTE"];
+    let lowercase = content.to_lowercase();
+    DIRECTIVES.iter().any(|directive| {
+        lowercase.starts_with(directive) || lowercase.contains(&format!(" {directive}"))
+    }) || NOTES.iter().any(|note| {
+        content
+            .strip_prefix(note)
+            .is_some_and(|rest| rest.starts_with([':', '(']))
+    })
+}
+
+/// Normalises the comments and reflows the paragraphs of prose
+/// comments, according to the config.
+///
+/// A paragraph is a run of prose comments, each on its own line
+/// with the same indentation. Only the paragraphs with a line
+/// longer than the line length are reflowed, so that the line breaks
+/// of the other paragraphs are kept. The comments do not count
+/// towards the width of the lines during formatting, so changing them
+/// here does not change the layout of the code.
+pub(crate) fn format_comments(
+    docs: Vec<SimpleDoc>,
+    config: &impl FormattingConfig,
+) -> Vec<SimpleDoc> {
+    if !config.space_after_comment_hash() && !config.reflow_comments() {
+        return docs;
+    }
+    let docs = docs.into_iter().map(|doc| match doc {
+        SimpleDoc::Text(text) if config.space_after_comment_hash() && text.starts_with('#') => {
+            match normalise_comment(&text) {
+                Cow::Owned(normalised) => SimpleDoc::Text(Rc::from(normalised)),
+                Cow::Borrowed(_) => SimpleDoc::Text(text),
+            }
+        }
+        doc => doc,
+    });
+    if !config.reflow_comments() {
+        return docs.collect();
+    }
+
+    // The lines of the output: their indentation and texts
+    let mut lines: Vec<(Option<usize>, Vec<Rc<str>>)> = vec![(None, vec![])];
+    for doc in docs {
+        match doc {
+            SimpleDoc::Line(indent) => lines.push((Some(indent), vec![])),
+            SimpleDoc::Text(text) if !text.is_empty() => lines
+                .last_mut()
+                .expect("There is always a line")
+                .1
+                .push(text),
+            SimpleDoc::Text(_) => {}
+        }
+    }
+    let prose_line = |(indent, texts): &(Option<usize>, Vec<Rc<str>>)| match texts.as_slice() {
+        [comment] if is_prose(comment) => Some((indent.unwrap_or(0), Rc::clone(comment))),
+        _ => None,
+    };
+
+    let mut reflowed = vec![];
+    let mut lines = lines.into_iter().peekable();
+    while let Some(line) = lines.next() {
+        let Some((indent, comment)) = prose_line(&line) else {
+            if let Some(indent) = line.0 {
+                reflowed.push(SimpleDoc::Line(indent));
+            }
+            reflowed.extend(line.1.into_iter().map(SimpleDoc::Text));
+            continue;
+        };
+        let mut paragraph = vec![comment];
+        while let Some((_, next_comment)) = lines
+            .peek()
+            .and_then(prose_line)
+            .filter(|(next_indent, _)| *next_indent == indent)
+        {
+            paragraph.push(next_comment);
+            lines.next();
+        }
+        let width = (config.line_length() as usize).saturating_sub(indent);
+        let paragraph: Vec<Rc<str>> = if paragraph
+            .iter()
+            .any(|comment| comment.chars().count() > width)
+        {
+            let words = paragraph
+                .iter()
+                .flat_map(|comment| comment["# ".len()..].split_whitespace());
+            wrap(words, width.saturating_sub("# ".len()))
+                .into_iter()
+                .map(|line| Rc::from(format!("# {line}")))
+                .collect()
+        } else {
+            paragraph
+        };
+        for (index, comment) in paragraph.into_iter().enumerate() {
+            if index > 0 || line.0.is_some() {
+                reflowed.push(SimpleDoc::Line(indent));
+            }
+            reflowed.push(SimpleDoc::Text(comment));
+        }
+    }
+    reflowed
+}
+
+/// Fills lines of at most `width` characters with the words.
+///
+/// A word longer than `width` gets a line of its own.
+fn wrap<'a>(words: impl Iterator<Item = &'a str>, width: usize) -> Vec<String> {
+    let mut lines: Vec<String> = vec![];
+    for word in words {
+        match lines.last_mut() {
+            Some(line) if line.chars().count() + 1 + word.chars().count() <= width => {
+                line.push(' ');
+                line.push_str(word);
+            }
+            _ => lines.push(word.to_string()),
+        }
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn inserts_a_space_after_the_hash() {
+        assert_eq!(normalise_comment("#comment"), "# comment");
+        assert_eq!(normalise_comment("#-----"), "# -----");
+        assert_eq!(normalise_comment("# comment"), "# comment");
+        assert_eq!(normalise_comment("#"), "#");
+    }
+
+    #[test]
+    fn keeps_special_comments() {
+        for comment in [
+            "#' @export",
+            "#!/usr/bin/env Rscript",
+            "#> [1] 1",
+            "## Section",
+            "#\tx",
+        ] {
+            assert_eq!(normalise_comment(comment), comment);
+        }
+    }
+
+    #[test]
+    fn detects_prose() {
+        let prose = [
+            "# This function computes the mean of the values.",
+            "# It returns NA (not NULL) for empty input.",
+            "# See the vignette for details.",
+            "# The values are weighted (see below), then summed.",
+        ];
+        for comment in prose {
+            assert!(is_prose(comment), "{comment}");
+        }
+        let not_prose = [
+            "# x <- mean(values)",
+            "# print(x)",
+            "# if (is.na(x)) {",
+            "# df |> filter(x)",
+            "# - a list item",
+            "# 1. a numbered item",
+            "#' @param x A vector.",
+            "## Section",
+            "#   indented",
+            "#",
+            "# a =",
+            "# x = 1",
+            "# values[1]",
+            "# mean(x)",
+            "# compute the mean(x) first",
+            "# (x)(y)",
+            "# total <- x +",
+            "# a &",
+            "# nolint start",
+            "# nolint end",
+            "# nocov start",
+            "# styler: off",
+            "# styler: on",
+            "# fmt: skip",
+            "# TODO: handle the missing values",
+            "# FIXME(someone): this is slow",
+        ];
+        for comment in not_prose {
+            assert!(!is_prose(comment), "{comment}");
+        }
+    }
+
+    #[test]
+    fn wraps_words() {
+        let words = "one two three four five".split_whitespace();
+        assert_eq!(wrap(words, 9), ["one two", "three", "four five"]);
+        let words = "a verylongword b".split_whitespace();
+        assert_eq!(wrap(words, 5), ["a", "verylongword", "b"]);
+    }
+}
@@ -10,6 +10,28 @@ pub trait FormattingConfig: std::fmt::Display + Clone {
     fn strip_suffix_whitespace_in_function_defs(&self) -> bool;
     fn function_line_breaks(&self) -> FunctionLineBreaks;
     fn insert_newline_in_quote_call(&self) -> bool;
+    fn semicolon_statements(&self) -> SemicolonStatements;
+    fn assignment_operator(&self) -> AssignmentOperator;
+    fn rewrite_right_assign(&self) -> bool;
+    fn quote_style(&self) -> QuoteStyle;
+    fn max_blank_lines(&self) -> usize;
+    fn max_blank_lines_top_level(&self) -> usize;
+    fn blank_lines_around_functions(&self) -> usize;
+    fn call_arguments_layout(&self) -> CallArgumentsLayout;
+    fn align_assignments(&self) -> bool;
+    fn align_named_arguments(&self) -> bool;
+    fn spaces_around_power(&self) -> bool;
+    fn spaces_around_slash(&self) -> bool;
+    fn space_in_special_ops(&self) -> bool;
+    fn brace_style(&self) -> BraceStyle;
+    fn else_on_new_line(&self) -> bool;
+    fn function_brace_on_new_line(&self) -> bool;
+    fn enforce_braces(&self) -> EnforceBraces;
+    fn remove_redundant_braces(&self) -> bool;
+    fn max_pipe_steps_on_one_line(&self) -> Option<usize>;
+    fn convert_magrittr_pipe(&self) -> bool;
+    fn space_after_comment_hash(&self) -> bool;
+    fn reflow_comments(&self) -> bool;
 }
 
 #[derive(Debug, Clone, Copy, Deserialize, Default, Eq, PartialEq)]
@@ -21,15 +43,119 @@ pub enum FunctionLineBreaks {
     Single,
 }
 
+#[derive(Debug, Clone, Copy, Deserialize, Default, Eq, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum SemicolonStatements {
+    #[default]
+    Split,
+    Inline,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize, Default, Eq, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum AssignmentOperator {
+    LeftArrow,
+    #[default]
+    Preserve,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize, Default, Eq, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum QuoteStyle {
+    Double,
+    Single,
+    #[default]
+    Preserve,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize, Default, Eq, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum CallArgumentsLayout {
+    #[default]
+    Fill,
+    OnePerLine,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize, Default, Eq, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum BraceStyle {
+    #[default]
+    Kr,
+    Allman,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize, Default, Eq, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum EnforceBraces {
+    Always,
+    Multiline,
+    #[default]
+    Preserve,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize, Default, Eq, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum EndOfLine {
+    #[default]
+    Lf,
+    Crlf,
+    Cr,
+}
+
+impl EndOfLine {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EndOfLine::Lf => "\n",
+            EndOfLine::Crlf => "\r\n",
+            EndOfLine::Cr => "\r",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Deserialize, Eq, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Style {
+    Tidyverse,
+    Bioconductor,
+    Google,
+}
+
+impl Style {
+    /// Returns the keys of the options set by the preset,
+    /// including the options it sets to their defaults.
+    pub fn keys(&self) -> &'static [&'static str] {
+        const TIDYVERSE: [&str; 5] = [
+            "indent",
+            "line_length",
+            "assignment_operator",
+            "quote_style",
+            "enforce_braces",
+        ];
+        match self {
+            Style::Tidyverse => &TIDYVERSE,
+            Style::Bioconductor | Style::Google => &[
+                "indent",
+                "line_length",
+                "assignment_operator",
+                "quote_style",
+                "enforce_braces",
+                "function_line_breaks",
+            ],
+        }
+    }
+}
+
 /// The configuration for `tergo`.
 ///
 /// This configuration can also read from a TOML file.
+/// The `style` key of the file selects one of the [Style] presets,
+/// and the other keys of the file override the preset.
 #[derive(Debug, Clone, Deserialize, Default)]
+#[serde(from = "ConfigFile")]
 pub struct Config {
     /// The number of characters to use for one level of indentation.
     ///
     /// Default: 2.
-    #[serde(default)]
     pub indent: Indent,
 
     /// Tha maximum number of characters in a line of the formatted
@@ -37,7 +163,6 @@ pub struct Config {
     /// if possible.
     ///
     /// Default: 120.
-    #[serde(default)]
     pub line_length: LineLength,
 
     /// A logical flag to determine whether to suppress line
@@ -53,7 +178,6 @@ pub struct Config {
     /// instead of inserting a new line after each `{`.
     ///
     /// Default: true.
-    #[serde(default)]
     pub embracing_op_no_nl: EmbracingOpNoNl,
 
     /// A logical flag indicating whether to insert new lines after
@@ -72,7 +196,6 @@ pub struct Config {
     /// in cases where the code does not fit in a single line.
     ///
     /// Default: false.
-    #[serde(default)]
     pub allow_nl_after_assignment: AllowNlAfterAssignment,
 
     /// A logical flag indicating whether to put a space before complex right hand sides of
@@ -89,7 +212,6 @@ pub struct Config {
     /// ```
     ///
     /// Default: true.
-    #[serde(default)]
     pub space_before_complex_rhs_in_formula: SpaceBeforeComplexRhsInFormulas,
 
     /// A logical flag indicating whether to keep the whitespace before the ending
@@ -111,7 +233,6 @@ pub struct Config {
     /// ```
     ///
     /// Default: true.
-    #[serde(default)]
     pub strip_suffix_whitespace_in_function_defs: StripSuffixWhitespaceInFunctionDefs,
 
     /// The type of line breaking inside function definitions'
@@ -139,7 +260,6 @@ pub struct Config {
     /// ```
     ///
     /// Default: `hanging`.
-    #[serde(default)]
     pub function_line_breaks: FunctionLineBreaks,
 
     /// A logical flag indicating whether to insert a new line after
@@ -166,9 +286,514 @@ pub struct Config {
     /// ```
     ///
     /// Default: true.
-    #[serde(default)]
     pub insert_newline_in_quote_call: InsertNewlineInQuoteCall,
 
+    /// The treatment of statements separated by semicolons.
+    /// Possible values are: `split`, `inline`. Split puts every
+    /// statement on its own line and removes the semicolons.
+    /// Inline keeps the statements that were on the same line
+    /// in the source on one line if they fit.
+    ///
+    /// Examples:
+    ///
+    /// ```R
+    /// # Input:
+    /// a <- 1; b <- 2
+    ///
+    /// # Split:
+    /// a <- 1
+    /// b <- 2
+    ///
+    /// # Inline:
+    /// a <- 1; b <- 2
+    /// ```
+    ///
+    /// Default: `split`.
+    pub semicolon_statements: SemicolonStatements,
+
+    /// The operator used for assignments with `=`.
+    /// Possible values are: `left_arrow`, `preserve`.
+    /// Left arrow replaces the assignments with `=` with `<-`.
+    /// Named arguments in function calls are not changed.
+    ///
+    /// Examples:
+    ///
+    /// ```R
+    /// # Input:
+    /// x = f(a = 1)
+    ///
+    /// # Left arrow:
+    /// x <- f(a = 1)
+    ///
+    /// # Preserve:
+    /// x = f(a = 1)
+    /// ```
+    ///
+    /// Default: `preserve`.
+    pub assignment_operator: AssignmentOperator,
+
+    /// A logical flag indicating whether to rewrite the right
+    /// assignments with `->` into left assignments with `<-`.
+    ///
+    /// The formatter outputs the following:
+    ///
+    /// ```R
+    /// 1 -> x # for rewrite_right_assign = false
+    /// # or
+    /// x <- 1 # for rewrite_right_assign = true
+    /// ```
+    ///
+    /// Default: false.
+    pub rewrite_right_assign: RewriteRightAssign,
+
+    /// The delimiters of string literals.
+    /// Possible values are: `double`, `single`, `preserve`.
+    /// Double and single rewrite the delimiters of strings
+    /// unless the string contains the new delimiter.
+    /// Raw strings are never changed.
+    ///
+    /// Examples:
+    ///
+    /// ```R
+    /// # Input:
+    /// c('a', "b", 'it\'s', 'say "hi"')
+    ///
+    /// # Double:
+    /// c("a", "b", "it's", 'say "hi"')
+    ///
+    /// # Single:
+    /// c('a', 'b', 'it\'s', 'say "hi"')
+    /// ```
+    ///
+    /// Default: `preserve`.
+    pub quote_style: QuoteStyle,
+
+    /// The maximum number of consecutive blank lines inside
+    /// function bodies and other blocks. Longer runs of blank
+    /// lines are collapsed.
+    ///
+    /// Examples:
+    ///
+    /// ```R
+    /// # Input:
+    /// f <- function() {
+    ///   a <- 1
+    ///
+    ///
+    ///
+    ///   b <- 2
+    /// }
+    ///
+    /// # For max_blank_lines = 1:
+    /// f <- function() {
+    ///   a <- 1
+    ///
+    ///   b <- 2
+    /// }
+    /// ```
+    ///
+    /// Default: 1.
+    pub max_blank_lines: MaxBlankLines,
+
+    /// The maximum number of consecutive blank lines between
+    /// top-level statements. Longer runs of blank lines are collapsed.
+    ///
+    /// Examples:
+    ///
+    /// ```R
+    /// # Input:
+    /// a <- 1
+    ///
+    ///
+    ///
+    ///
+    /// # Section
+    /// b <- 2
+    ///
+    /// # For max_blank_lines_top_level = 2:
+    /// a <- 1
+    ///
+    ///
+    /// # Section
+    /// b <- 2
+    /// ```
+    ///
+    /// Default: 2.
+    pub max_blank_lines_top_level: MaxBlankLinesTopLevel,
+
+    /// The minimum number of blank lines before and after
+    /// a function definition. Zero keeps the blank lines
+    /// of the source. The blank lines are still capped by
+    /// `max_blank_lines` and `max_blank_lines_top_level`.
+    ///
+    /// Examples:
+    ///
+    /// ```R
+    /// # Input:
+    /// a <- 1
+    /// f <- function() NULL
+    /// b <- 2
+    ///
+    /// # For blank_lines_around_functions = 1:
+    /// a <- 1
+    ///
+    /// f <- function() NULL
+    ///
+    /// b <- 2
+    /// ```
+    ///
+    /// Default: 0.
+    pub blank_lines_around_functions: BlankLinesAroundFunctions,
+
+    /// The layout of the arguments of calls that do not fit
+    /// on one line. Possible values are: `fill`, `one_per_line`.
+    /// Fill lets the arguments share a line if they fit, for example
+    /// before a function passed as the last argument. One per line
+    /// puts every argument on its own line and the closing
+    /// parenthesis on its own line.
+    ///
+    /// Examples:
+    ///
+    /// ```R
+    /// # Fill:
+    /// result <- some_function_name(
+    ///   first_argument, second_argument, function(x) {
+    ///   x
+    /// }
+    /// )
+    ///
+    /// # One per line:
+    /// result <- some_function_name(
+    ///   first_argument,
+    ///   second_argument,
+    ///   function(x) {
+    ///     x
+    ///   }
+    /// )
+    /// ```
+    ///
+    /// Default: `fill`.
+    pub call_arguments_layout: CallArgumentsLayout,
+
+    /// A logical flag indicating whether to align the assignment
+    /// operators of consecutive assignments. A blank line or
+    /// a statement other than an assignment ends the block
+    /// of aligned assignments.
+    ///
+    /// The formatter outputs the following:
+    ///
+    /// ```R
+    /// # For align_assignments = false
+    /// a <- 1
+    /// bbbb <- 2
+    ///
+    /// # For align_assignments = true
+    /// a    <- 1
+    /// bbbb <- 2
+    /// ```
+    ///
+    /// Default: false.
+    pub align_assignments: AlignAssignments,
+
+    /// A logical flag indicating whether to align the `=` of
+    /// consecutive named arguments of calls broken into
+    /// multiple lines.
+    ///
+    /// The formatter outputs the following:
+    ///
+    /// ```R
+    /// # For align_named_arguments = false
+    /// list(
+    ///   a = 1,
+    ///   bbbb = 2
+    /// )
+    ///
+    /// # For align_named_arguments = true
+    /// list(
+    ///   a    = 1,
+    ///   bbbb = 2
+    /// )
+    /// ```
+    ///
+    /// Default: false.
+    pub align_named_arguments: AlignNamedArguments,
+
+    /// A logical flag indicating whether to put spaces around `^`.
+    ///
+    /// The formatter outputs the following:
+    ///
+    /// ```R
+    /// x^2 # for spaces_around_power = false
+    /// # or
+    /// x ^ 2 # for spaces_around_power = true
+    /// ```
+    ///
+    /// Default: false.
+    pub spaces_around_power: SpacesAroundPower,
+
+    /// A logical flag indicating whether to put spaces around `/`.
+    ///
+    /// The formatter outputs the following:
+    ///
+    /// ```R
+    /// x / 2 # for spaces_around_slash = true
+    /// # or
+    /// x/2 # for spaces_around_slash = false
+    /// ```
+    ///
+    /// Default: true.
+    pub spaces_around_slash: SpacesAroundSlash,
+
+    /// A logical flag indicating whether to put spaces around
+    /// the special operators such as `%in%` and `%o%`.
+    ///
+    /// The magrittr pipes `%>%`, `%<>%`, `%T>%` and `%$%` are not covered
+    /// and always get spaces, like `|>`.
+    ///
+    /// The formatter outputs the following:
+    ///
+    /// ```R
+    /// x %in% y # for space_in_special_ops = true
+    /// # or
+    /// x%in%y # for space_in_special_ops = false
+    /// ```
+    ///
+    /// Default: true.
+    pub space_in_special_ops: SpaceInSpecialOps,
+
+    /// The placement of the opening brace of the bodies
+    /// of `if`, `else`, `for`, `while` and `repeat`.
+    ///
+    /// The formatter outputs the following:
+    ///
+    /// ```R
+    /// # For brace_style = "kr"
+    /// if (x) {
+    ///   y
+    /// }
+    ///
+    /// # For brace_style = "allman"
+    /// if (x)
+    /// {
+    ///   y
+    /// }
+    /// ```
+    ///
+    /// Possible values: "kr", "allman".
+    ///
+    /// Default: "kr".
+    pub brace_style: BraceStyle,
+
+    /// A logical flag indicating whether to put `else` on a new line
+    /// after the closing brace of the `if` body.
+    ///
+    /// R only parses such an `else` inside braces, so top-level
+    /// `if` expressions always keep `else` on the same line.
+    ///
+    /// The formatter outputs the following:
+    ///
+    /// ```R
+    /// # For else_on_new_line = false
+    /// if (x) {
+    ///   y
+    /// } else {
+    ///   z
+    /// }
+    ///
+    /// # For else_on_new_line = true
+    /// if (x) {
+    ///   y
+    /// }
+    /// else {
+    ///   z
+    /// }
+    /// ```
+    ///
+    /// Default: false.
+    pub else_on_new_line: ElseOnNewLine,
+
+    /// A logical flag indicating whether to put the opening brace
+    /// of functions assigned with `<-`, `<<-` or `=` on a new line.
+    ///
+    /// The functions passed as named arguments of a call keep their brace
+    /// on the line of the name. `->` is not covered: R parses
+    /// `function(x) {x} -> f` as a function whose body assigns `{x}` to `f`.
+    ///
+    /// The formatter outputs the following:
+    ///
+    /// ```R
+    /// # For function_brace_on_new_line = false
+    /// f <- function(x) {
+    ///   x
+    /// }
+    ///
+    /// # For function_brace_on_new_line = true
+    /// f <- function(x)
+    /// {
+    ///   x
+    /// }
+    /// ```
+    ///
+    /// Default: false.
+    pub function_brace_on_new_line: FunctionBraceOnNewLine,
+
+    /// When to add braces around the bodies of `if`, `for` and `while`.
+    ///
+    /// With "multiline", the bodies containing braces, comments
+    /// or control flow get braces, and if one branch of an `if`
+    /// has braces, all of them do. With "always", every body gets
+    /// braces. "preserve" leaves the bodies as they are.
+    ///
+    /// The formatter outputs the following:
+    ///
+    /// ```R
+    /// # For enforce_braces = "preserve"
+    /// if (x) y
+    ///
+    /// # For enforce_braces = "always"
+    /// if (x) {
+    ///   y
+    /// }
+    /// ```
+    ///
+    /// Possible values: "always", "multiline", "preserve".
+    ///
+    /// Default: "preserve".
+    pub enforce_braces: EnforceBraces,
+
+    /// A logical flag indicating whether to remove the braces around
+    /// the body of an `if` without `else` whose body is a single statement
+    /// that fits on one line.
+    ///
+    /// Has no effect with `enforce_braces = "always"`.
+    ///
+    /// The formatter outputs the following:
+    ///
+    /// ```R
+    /// if (x) {
+    ///   y
+    /// } # for remove_redundant_braces = false
+    /// # or
+    /// if (x) y # for remove_redundant_braces = true
+    /// ```
+    ///
+    /// Default: false.
+    pub remove_redundant_braces: RemoveRedundantBraces,
+
+    /// The maximum number of steps of a pipe chain kept on one line.
+    ///
+    /// Every step of a longer chain goes on its own line,
+    /// even if the chain fits on one line. If not set,
+    /// a chain is broken only when it does not fit.
+    ///
+    /// The formatter outputs the following:
+    ///
+    /// ```R
+    /// # For max_pipe_steps_on_one_line = 1
+    /// df |> filter(x)
+    /// df |>
+    ///   filter(x) |>
+    ///   select(y)
+    /// ```
+    ///
+    /// Default: not set.
+    pub max_pipe_steps_on_one_line: MaxPipeStepsOnOneLine,
+
+    /// A logical flag indicating whether to replace `%>%` with `|>`.
+    ///
+    /// Only the steps calling a function without the `.` placeholder
+    /// are rewritten, because the other steps behave differently
+    /// with `|>`.
+    ///
+    /// The formatter outputs the following:
+    ///
+    /// ```R
+    /// df %>% filter(x) %>% lm(y ~ x, data = .) # for convert_magrittr_pipe = false
+    /// # or
+    /// df |> filter(x) %>% lm(y ~ x, data = .) # for convert_magrittr_pipe = true
+    /// ```
+    ///
+    /// Default: false.
+    pub convert_magrittr_pipe: ConvertMagrittrPipe,
+
+    /// A logical flag indicating whether to insert a space after
+    /// the `#` of comments.
+    ///
+    /// The comments starting with `#'`, `#!`, `#>` and `##`
+    /// stay as they are.
+    ///
+    /// The formatter outputs the following:
+    ///
+    /// ```R
+    /// #comment # for space_after_comment_hash = false
+    /// # or
+    /// # comment # for space_after_comment_hash = true
+    /// ```
+    ///
+    /// Default: false.
+    pub space_after_comment_hash: SpaceAfterCommentHash,
+
+    /// A logical flag indicating whether to reflow the comments
+    /// written in prose to fit the line length.
+    ///
+    /// Only the paragraphs with a line longer than the line length
+    /// are reflowed. Commented-out code, lists and roxygen comments
+    /// stay as they are.
+    ///
+    /// The formatter outputs the following for `line_length = 30`:
+    ///
+    /// ```R
+    /// # For reflow_comments = false
+    /// # This comment is a bit too long to fit.
+    ///
+    /// # For reflow_comments = true
+    /// # This comment is a bit too
+    /// # long to fit.
+    /// ```
+    ///
+    /// Default: false.
+    pub reflow_comments: ReflowComments,
+
+    /// A logical flag indicating whether to format the roxygen comments.
+    ///
+    /// The spaces in the tags are collapsed, the paragraphs with a line
+    /// longer than the line length are wrapped, and the code
+    /// of `@examples` and `@examplesIf` is formatted.
+    ///
+    /// The formatter outputs the following:
+    ///
+    /// ```R
+    /// # For format_roxygen = false
+    /// #' @param x  The input.
+    /// #' @examples
+    /// #' f(x=1)
+    ///
+    /// # For format_roxygen = true
+    /// #' @param x The input.
+    /// #' @examples
+    /// #' f(x = 1)
+    /// ```
+    ///
+    /// Default: false.
+    pub format_roxygen: FormatRoxygen,
+
+    /// The line ending of the formatted code.
+    ///
+    /// Possible values:
+    /// * lf - `\n`, as on Linux and macOS
+    /// * crlf - `\r\n`, as on Windows
+    /// * cr - `\r`
+    ///
+    /// Default: lf.
+    pub end_of_line: EndOfLine,
+
+    /// A logical flag indicating whether the formatted code
+    /// ends with a line ending. If false, the formatted code
+    /// ends with its last character.
+    ///
+    /// Default: true.
+    pub insert_final_newline: InsertFinalNewline,
+
     /// A list of file paths to exclude from formatting.
     ///
     /// The file paths are relative to the directory
@@ -184,8 +809,97 @@ pub struct Config {
     /// "./unguentum",
     /// "./antidotum/tergo/R/extendr-wrappers.R",
     /// "./target"]
-    #[serde(default)]
     pub exclusion_list: ExclusionList,
+
+    /// A list of the file names and extensions of the R scripts
+    /// to format, besides the files with the `.R` extension.
+    ///
+    /// An entry starting with a dot matches the file names
+    /// ending with it, such as `.Rprofile`. Other entries match
+    /// the whole file name, such as `Rprofile.site`.
+    ///
+    /// Example values:
+    ///
+    /// include = [".Rprofile", "Rprofile.site"]
+    pub include: IncludeList,
+
+    /// A logical flag indicating whether to format the files
+    /// without an extension starting with an Rscript shebang,
+    /// such as `#!/usr/bin/env Rscript`.
+    ///
+    /// Default: false.
+    pub include_shebang_scripts: IncludeShebangScripts,
+}
+
+/// Declares the contents of a configuration file, in which every key
+/// is optional, its conversion to [Config] and the display of [Config]
+/// as the resolved values of all the keys.
+macro_rules! config_file {
+    ($($field:ident: $type:ty,)*) => {
+        #[derive(Deserialize)]
+        struct ConfigFile {
+            style: Option<Style>,
+            $($field: Option<$type>,)*
+        }
+
+        impl From<ConfigFile> for Config {
+            fn from(file: ConfigFile) -> Self {
+                let mut config = file.style.map_or_else(Config::default, Config::from_style);
+                $(
+                    if let Some(value) = file.$field {
+                        config.$field = value;
+                    }
+                )*
+                config
+            }
+        }
+
+        impl std::fmt::Display for Config {
+            fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+                let options = [$(format!("{}: {:?}", stringify!($field), self.$field),)*];
+                f.write_str(&options.join(" "))
+            }
+        }
+    };
+}
+
+config_file! {
+    indent: Indent,
+    line_length: LineLength,
+    embracing_op_no_nl: EmbracingOpNoNl,
+    allow_nl_after_assignment: AllowNlAfterAssignment,
+    space_before_complex_rhs_in_formula: SpaceBeforeComplexRhsInFormulas,
+    strip_suffix_whitespace_in_function_defs: StripSuffixWhitespaceInFunctionDefs,
+    function_line_breaks: FunctionLineBreaks,
+    insert_newline_in_quote_call: InsertNewlineInQuoteCall,
+    semicolon_statements: SemicolonStatements,
+    assignment_operator: AssignmentOperator,
+    rewrite_right_assign: RewriteRightAssign,
+    quote_style: QuoteStyle,
+    max_blank_lines: MaxBlankLines,
+    max_blank_lines_top_level: MaxBlankLinesTopLevel,
+    blank_lines_around_functions: BlankLinesAroundFunctions,
+    call_arguments_layout: CallArgumentsLayout,
+    align_assignments: AlignAssignments,
+    align_named_arguments: AlignNamedArguments,
+    spaces_around_power: SpacesAroundPower,
+    spaces_around_slash: SpacesAroundSlash,
+    space_in_special_ops: SpaceInSpecialOps,
+    brace_style: BraceStyle,
+    else_on_new_line: ElseOnNewLine,
+    function_brace_on_new_line: FunctionBraceOnNewLine,
+    enforce_braces: EnforceBraces,
+    remove_redundant_braces: RemoveRedundantBraces,
+    max_pipe_steps_on_one_line: MaxPipeStepsOnOneLine,
+    convert_magrittr_pipe: ConvertMagrittrPipe,
+    space_after_comment_hash: SpaceAfterCommentHash,
+    reflow_comments: ReflowComments,
+    format_roxygen: FormatRoxygen,
+    end_of_line: EndOfLine,
+    insert_final_newline: InsertFinalNewline,
+    exclusion_list: ExclusionList,
+    include: IncludeList,
+    include_shebang_scripts: IncludeShebangScripts,
 }
 
 #[derive(Debug, Deserialize, Clone, Copy)]
@@ -238,9 +952,103 @@ impl Default for InsertNewlineInQuoteCall {
     }
 }
 
+#[derive(Debug, Deserialize, Clone, Copy, Default)]
+pub struct RewriteRightAssign(pub bool);
+
+#[derive(Debug, Deserialize, Clone, Copy)]
+pub struct MaxBlankLines(pub usize);
+impl Default for MaxBlankLines {
+    fn default() -> Self {
+        Self(1)
+    }
+}
+
+#[derive(Debug, Deserialize, Clone, Copy)]
+pub struct MaxBlankLinesTopLevel(pub usize);
+impl Default for MaxBlankLinesTopLevel {
+    fn default() -> Self {
+        Self(2)
+    }
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, Default)]
+pub struct BlankLinesAroundFunctions(pub usize);
+
+#[derive(Debug, Deserialize, Clone, Copy, Default)]
+pub struct AlignAssignments(pub bool);
+
+#[derive(Debug, Deserialize, Clone, Copy, Default)]
+pub struct AlignNamedArguments(pub bool);
+
+#[derive(Debug, Deserialize, Clone, Copy, Default)]
+pub struct SpacesAroundPower(pub bool);
+
+#[derive(Debug, Deserialize, Clone, Copy)]
+pub struct SpacesAroundSlash(pub bool);
+impl Default for SpacesAroundSlash {
+    fn default() -> Self {
+        Self(true)
+    }
+}
+
+#[derive(Debug, Deserialize, Clone, Copy)]
+pub struct SpaceInSpecialOps(pub bool);
+impl Default for SpaceInSpecialOps {
+    fn default() -> Self {
+        Self(true)
+    }
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, Default)]
+pub struct ElseOnNewLine(pub bool);
+
+#[derive(Debug, Deserialize, Clone, Copy, Default)]
+pub struct FunctionBraceOnNewLine(pub bool);
+
+#[derive(Debug, Deserialize, Clone, Copy, Default)]
+pub struct RemoveRedundantBraces(pub bool);
+
+#[derive(Debug, Deserialize, Clone, Copy, Default)]
+pub struct MaxPipeStepsOnOneLine(pub Option<usize>);
+
+#[derive(Debug, Deserialize, Clone, Copy, Default)]
+pub struct ConvertMagrittrPipe(pub bool);
+
+#[derive(Debug, Deserialize, Clone, Copy, Default)]
+pub struct SpaceAfterCommentHash(pub bool);
+
+#[derive(Debug, Deserialize, Clone, Copy, Default)]
+pub struct ReflowComments(pub bool);
+
+#[derive(Debug, Deserialize, Clone, Copy, Default)]
+pub struct FormatRoxygen(pub bool);
+
+#[derive(Debug, Deserialize, Clone, Copy)]
+pub struct InsertFinalNewline(pub bool);
+impl Default for InsertFinalNewline {
+    fn default() -> Self {
+        Self(true)
+    }
+}
+
 #[derive(Debug, Deserialize, Clone, Default)]
 pub struct ExclusionList(pub Vec<String>);
 
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct IncludeList(pub Vec<String>);
+
+impl IncludeList {
+    /// Returns whether the file name matches one of the entries.
+    pub fn matches(&self, file_name: &str) -> bool {
+        self.0.iter().any(|entry| {
+            file_name == entry || (entry.starts_with('.') && file_name.ends_with(entry.as_str()))
+        })
+    }
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, Default)]
+pub struct IncludeShebangScripts(pub bool);
+
 impl FormattingConfig for Config {
     fn line_length(&self) -> i32 {
         self.line_length.0
@@ -273,14 +1081,93 @@ impl FormattingConfig for Config {
     fn insert_newline_in_quote_call(&self) -> bool {
         self.insert_newline_in_quote_call.0
     }
-}
 
-impl std::fmt::Display for Config {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        f.write_fmt(format_args!(
-            "indent: {} line_length: {} allow_nl_after_assignment: {}",
-            self.indent.0, self.line_length.0, self.allow_nl_after_assignment.0
-        ))
+    fn semicolon_statements(&self) -> SemicolonStatements {
+        self.semicolon_statements
+    }
+
+    fn assignment_operator(&self) -> AssignmentOperator {
+        self.assignment_operator
+    }
+
+    fn rewrite_right_assign(&self) -> bool {
+        self.rewrite_right_assign.0
+    }
+
+    fn quote_style(&self) -> QuoteStyle {
+        self.quote_style
+    }
+
+    fn max_blank_lines(&self) -> usize {
+        self.max_blank_lines.0
+    }
+
+    fn max_blank_lines_top_level(&self) -> usize {
+        self.max_blank_lines_top_level.0
+    }
+
+    fn blank_lines_around_functions(&self) -> usize {
+        self.blank_lines_around_functions.0
+    }
+
+    fn call_arguments_layout(&self) -> CallArgumentsLayout {
+        self.call_arguments_layout
+    }
+
+    fn align_assignments(&self) -> bool {
+        self.align_assignments.0
+    }
+
+    fn align_named_arguments(&self) -> bool {
+        self.align_named_arguments.0
+    }
+
+    fn spaces_around_power(&self) -> bool {
+        self.spaces_around_power.0
+    }
+
+    fn spaces_around_slash(&self) -> bool {
+        self.spaces_around_slash.0
+    }
+
+    fn space_in_special_ops(&self) -> bool {
+        self.space_in_special_ops.0
+    }
+
+    fn brace_style(&self) -> BraceStyle {
+        self.brace_style
+    }
+
+    fn else_on_new_line(&self) -> bool {
+        self.else_on_new_line.0
+    }
+
+    fn function_brace_on_new_line(&self) -> bool {
+        self.function_brace_on_new_line.0
+    }
+
+    fn enforce_braces(&self) -> EnforceBraces {
+        self.enforce_braces
+    }
+
+    fn remove_redundant_braces(&self) -> bool {
+        self.remove_redundant_braces.0
+    }
+
+    fn max_pipe_steps_on_one_line(&self) -> Option<usize> {
+        self.max_pipe_steps_on_one_line.0
+    }
+
+    fn convert_magrittr_pipe(&self) -> bool {
+        self.convert_magrittr_pipe.0
+    }
+
+    fn space_after_comment_hash(&self) -> bool {
+        self.space_after_comment_hash.0
+    }
+
+    fn reflow_comments(&self) -> bool {
+        self.reflow_comments.0
     }
 }
 
@@ -310,7 +1197,69 @@ impl Config {
             ),
             function_line_breaks,
             insert_newline_in_quote_call: InsertNewlineInQuoteCall(insert_newline_in_quote_call),
+            semicolon_statements: SemicolonStatements::default(),
+            assignment_operator: AssignmentOperator::default(),
+            rewrite_right_assign: RewriteRightAssign::default(),
+            quote_style: QuoteStyle::default(),
+            max_blank_lines: MaxBlankLines::default(),
+            max_blank_lines_top_level: MaxBlankLinesTopLevel::default(),
+            blank_lines_around_functions: BlankLinesAroundFunctions::default(),
+            call_arguments_layout: CallArgumentsLayout::default(),
+            align_assignments: AlignAssignments::default(),
+            align_named_arguments: AlignNamedArguments::default(),
+            spaces_around_power: SpacesAroundPower::default(),
+            spaces_around_slash: SpacesAroundSlash::default(),
+            space_in_special_ops: SpaceInSpecialOps::default(),
+            brace_style: BraceStyle::default(),
+            else_on_new_line: ElseOnNewLine::default(),
+            function_brace_on_new_line: FunctionBraceOnNewLine::default(),
+            enforce_braces: EnforceBraces::default(),
+            remove_redundant_braces: RemoveRedundantBraces::default(),
+            max_pipe_steps_on_one_line: MaxPipeStepsOnOneLine::default(),
+            convert_magrittr_pipe: ConvertMagrittrPipe::default(),
+            space_after_comment_hash: SpaceAfterCommentHash::default(),
+            reflow_comments: ReflowComments::default(),
+            format_roxygen: FormatRoxygen::default(),
+            end_of_line: EndOfLine::default(),
+            insert_final_newline: InsertFinalNewline::default(),
             exclusion_list: ExclusionList(exclusion_list),
+            include: IncludeList::default(),
+            include_shebang_scripts: IncludeShebangScripts::default(),
+        }
+    }
+
+    /// Returns the configuration of a style preset.
+    ///
+    /// * tidyverse follows the [tidyverse style guide](https://style.tidyverse.org/).
+    /// * bioconductor follows the
+    ///   [Bioconductor style guide](https://contributions.bioconductor.org/r-code.html)
+    ///   and indents with 4 spaces.
+    /// * google follows the [Google R style guide](https://google.github.io/styleguide/Rguide.html)
+    ///   and puts braces around all bodies of control flow.
+    ///
+    /// The options not mentioned by the style guide keep their defaults.
+    /// [Style::keys] lists the options set by each preset.
+    pub fn from_style(style: Style) -> Self {
+        let tidyverse = Self {
+            indent: Indent(2),
+            line_length: LineLength(80),
+            assignment_operator: AssignmentOperator::LeftArrow,
+            quote_style: QuoteStyle::Double,
+            enforce_braces: EnforceBraces::Multiline,
+            ..Self::default()
+        };
+        match style {
+            Style::Tidyverse => tidyverse,
+            Style::Bioconductor => Self {
+                indent: Indent(4),
+                function_line_breaks: FunctionLineBreaks::Double,
+                ..tidyverse
+            },
+            Style::Google => Self {
+                function_line_breaks: FunctionLineBreaks::Double,
+                enforce_braces: EnforceBraces::Always,
+                ..tidyverse
+            },
         }
     }
 }
@@ -1,5 +1,5 @@
 // Implementing Wadler and https://lindig.github.io/papers/strictly-pretty-2000.pdf
-use std::collections::{HashSet, VecDeque};
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::ops::Add;
 use std::rc::Rc;
 
@@ -93,6 +93,13 @@ pub(crate) enum Doc {
     // essentially forcing the groups containing it
     // to break new lines
     HardBreak,
+    // A cell is padded with spaces to the width of the widest cell
+    // of its block, e.g. the left-hand sides of consecutive assignments.
+    // A cell that is not part of any block is not padded.
+    Align(Rc<Doc>, CommonProperties), // inner doc, props with the cell reference
+    // Declares the paddings of the cells of a block. It has no width.
+    // The cells are padded only if the observed doc breaks, if there is one.
+    AlignBlock(Rc<[(usize, usize)]>, Option<usize>), // (cell reference, padding), observed doc
 }
 
 impl std::fmt::Display for Doc {
@@ -113,6 +120,8 @@ impl std::fmt::Display for Doc {
                 common_props.1, common_props.0, inside.1, inside.0
             )),
             Doc::HardBreak => f.write_str("HardBreak"),
+            Doc::Align(body, props) => write!(f, "Align{}({body})", props.1),
+            Doc::AlignBlock(cells, observed) => write!(f, "AlignBlock{cells:?}:{observed:?}"),
         }
     }
 }
@@ -129,6 +138,8 @@ pub(crate) fn query_inline_position(doc: &Doc) -> InlineCommentPosition {
         Doc::Break(_) => InlineCommentPosition::No,
         Doc::Group(_, props) => props.0,
         Doc::HardBreak => InlineCommentPosition::No,
+        Doc::Align(_, props) => props.0,
+        Doc::AlignBlock(_, _) => InlineCommentPosition::No,
     }
 }
 
@@ -139,6 +150,7 @@ pub trait DocAlgebra {
     fn nest_if_break(self, indent: i32, observed_doc: usize) -> Rc<Doc>;
     fn nest_hanging(self) -> Rc<Doc>;
     fn fits_until_l_bracket(self) -> Rc<Doc>;
+    fn to_align_cell(self, doc_ref: &mut usize) -> Rc<Doc>;
 }
 
 impl DocAlgebra for Rc<Doc> {
@@ -185,6 +197,82 @@ impl DocAlgebra for Rc<Doc> {
         let properties = CommonProperties(query_inline_position(&self), 0);
         Rc::new(Doc::FitsUntilLBracket(self, properties))
     }
+
+    fn to_align_cell(self, doc_ref: &mut usize) -> Rc<Doc> {
+        *doc_ref += 1;
+        let properties = CommonProperties(query_inline_position(&self), *doc_ref);
+        Rc::new(Doc::Align(self, properties))
+    }
+}
+
+/// Returns the declaration of the paddings that align the first cells
+/// of the rows. The rows without cells are not aligned.
+///
+/// `observed_doc` is the group which needs to break for the cells
+/// to be padded, e.g. the arguments of a call.
+pub(crate) fn align_rows(rows: &[Rc<Doc>], observed_doc: Option<usize>) -> Rc<Doc> {
+    let cells: Vec<(usize, usize)> = rows
+        .iter()
+        .filter_map(|row| first_align_cell(row))
+        .filter_map(|(cell, inner)| {
+            let mut width = 0;
+            last_line_width(inner, false, &mut width).map(|_| (cell, width))
+        })
+        .collect();
+    if cells.len() < 2 {
+        return Rc::new(Doc::Nil);
+    }
+    let max_width = cells.iter().map(|(_, width)| *width).max().unwrap_or(0);
+    Rc::new(Doc::AlignBlock(
+        cells
+            .into_iter()
+            .map(|(cell, width)| (cell, max_width - width))
+            .collect(),
+        observed_doc,
+    ))
+}
+
+fn first_align_cell(doc: &Doc) -> Option<(usize, &Doc)> {
+    match doc {
+        Doc::Align(inner, props) => Some((props.1, inner)),
+        Doc::Cons(first, second, _) => first_align_cell(first).or_else(|| first_align_cell(second)),
+        Doc::Nest(_, inner, _)
+        | Doc::NestIfBreak(_, inner, _, _)
+        | Doc::NestHanging(inner, _)
+        | Doc::FitsUntilLBracket(inner, _)
+        | Doc::Group(GroupDocProperties(inner, _), _) => first_align_cell(inner),
+        Doc::Nil | Doc::Text(_, _, _) | Doc::Break(_) | Doc::HardBreak | Doc::AlignBlock(_, _) => {
+            None
+        }
+    }
+}
+
+/// Adds the width of the last line of the doc to `width`. The groups
+/// that always break start new lines. Returns None if the doc
+/// contains a hard break, e.g. an inline comment.
+fn last_line_width(doc: &Doc, is_broken: bool, width: &mut usize) -> Option<()> {
+    match doc {
+        Doc::Nil | Doc::AlignBlock(_, _) => {}
+        Doc::Cons(first, second, _) => {
+            last_line_width(first, is_broken, width)?;
+            last_line_width(second, is_broken, width)?;
+        }
+        Doc::Text(_, text_width, _) => *width += text_width,
+        Doc::Nest(_, inner, _)
+        | Doc::NestIfBreak(_, inner, _, _)
+        | Doc::NestHanging(inner, _)
+        | Doc::FitsUntilLBracket(inner, _)
+        | Doc::Align(inner, _) => last_line_width(inner, is_broken, width)?,
+        Doc::Break(_) if is_broken => *width = 0,
+        Doc::Break(s) => *width += s.len(),
+        Doc::Group(GroupDocProperties(inner, should_break), _) => last_line_width(
+            inner,
+            matches!(should_break, ShouldBreak::Yes | ShouldBreak::Propagate),
+            width,
+        )?,
+        Doc::HardBreak => return None,
+    }
+    Some(())
 }
 
 pub(crate) struct DocBuffer<'a>(pub(crate) &'a VecDeque<(i32, Mode, Rc<Doc>)>);
@@ -220,6 +308,67 @@ pub(crate) fn it_simple_doc_to_string(docs: &[SimpleDoc]) -> String {
     answer
 }
 
+/// Collapses the runs of blank lines longer than allowed by the config.
+///
+/// A run of blank lines is top-level if all of its line breaks
+/// are not indented. The texts between the line breaks of a run
+/// are whitespace, so only the texts after the last line break
+/// are kept when a run is collapsed.
+pub(crate) fn cap_blank_lines(
+    docs: Vec<SimpleDoc>,
+    config: &impl FormattingConfig,
+) -> Vec<SimpleDoc> {
+    fn flush(
+        run: &mut Vec<SimpleDoc>,
+        capped: &mut Vec<SimpleDoc>,
+        config: &impl FormattingConfig,
+    ) {
+        let indents: Vec<usize> = run
+            .iter()
+            .filter_map(|doc| match doc {
+                SimpleDoc::Line(indent) => Some(*indent),
+                SimpleDoc::Text(_) => None,
+            })
+            .collect();
+        // The first line break of a run ends the line with code
+        let blank_lines = if capped.is_empty() {
+            indents.len()
+        } else {
+            indents.len().saturating_sub(1)
+        };
+        let max_blank_lines = if indents.iter().all(|indent| *indent == 0) {
+            config.max_blank_lines_top_level()
+        } else {
+            config.max_blank_lines()
+        };
+        if blank_lines <= max_blank_lines {
+            capped.append(run);
+            return;
+        }
+        let last_line = run
+            .iter()
+            .rposition(|doc| matches!(doc, SimpleDoc::Line(_)))
+            .expect("A run with blank lines has line breaks");
+        let kept_lines = indents.len() - (blank_lines - max_blank_lines);
+        capped.extend(std::iter::repeat_n(run[last_line].clone(), kept_lines));
+        capped.extend(run.drain(..).skip(last_line + 1));
+    }
+
+    let mut capped = Vec::with_capacity(docs.len());
+    let mut run = vec![];
+    for doc in docs {
+        match &doc {
+            SimpleDoc::Text(s) if !s.chars().all(|c| c == ' ') => {
+                flush(&mut run, &mut capped, config);
+                capped.push(doc);
+            }
+            _ => run.push(doc),
+        }
+    }
+    flush(&mut run, &mut capped, config);
+    capped
+}
+
 /// `broken_docs` is a set of all the docs that are being formatted
 /// with line breaks. This set is continuously being filled up during
 /// execution of `format_to_sdoc`.
@@ -231,6 +380,7 @@ pub(crate) fn it_format_to_sdoc(
 ) -> Vec<SimpleDoc> {
     let line_length = config.line_length();
     let mut simple_docs = Vec::new();
+    let mut paddings: HashMap<usize, (usize, Option<usize>)> = HashMap::new();
     while let Some(doc) = docs.pop_front() {
         let (indent, mode, doc) = doc;
         match (indent, mode, &*doc) {
@@ -288,6 +438,27 @@ pub(crate) fn it_format_to_sdoc(
                 }
             }
             (_, _, Doc::HardBreak) => {}
+            (i, m, Doc::Align(inner, CommonProperties(_, cell))) => {
+                if let Some((padding, observed_doc)) = paddings.get(cell) {
+                    if observed_doc.is_none_or(|observed_doc| broken_docs.contains(&observed_doc)) {
+                        docs.push_front((
+                            i,
+                            m,
+                            Rc::new(Doc::Text(
+                                Rc::from(" ".repeat(*padding)),
+                                *padding,
+                                CommonProperties::default(),
+                            )),
+                        ));
+                    }
+                }
+                docs.push_front((i, m, Rc::clone(inner)));
+            }
+            (_, _, Doc::AlignBlock(cells, observed_doc)) => {
+                for (cell, padding) in cells.iter() {
+                    paddings.insert(*cell, (*padding, *observed_doc));
+                }
+            }
         }
     }
     simple_docs
@@ -328,10 +499,11 @@ fn fits(mut remaining_width: i32, mut docs: VecDeque<Triple>) -> bool {
                     docs.push_front((i + step, m, Rc::clone(doc)));
                     continue;
                 }
-                (i, m, Doc::NestHanging(doc, _)) => {
+                (i, m, Doc::NestHanging(doc, _)) | (i, m, Doc::Align(doc, _)) => {
                     docs.push_front((i, m, Rc::clone(doc)));
                     continue;
                 }
+                (_, _, Doc::AlignBlock(_, _)) => continue,
                 (_, _, Doc::Text(_, s_len, _)) => {
                     remaining_width -= *s_len as i32;
                     continue;
@@ -395,10 +567,11 @@ fn fits_until_l_bracket(mut remaining_width: i32, mut docs: VecDeque<Triple>) ->
                     docs.push_front((i + step, m, Rc::clone(doc)));
                     continue;
                 }
-                (i, m, Doc::NestHanging(doc, _)) => {
+                (i, m, Doc::NestHanging(doc, _)) | (i, m, Doc::Align(doc, _)) => {
                     docs.push_front((i, m, Rc::clone(doc)));
                     continue;
                 }
+                (_, _, Doc::AlignBlock(_, _)) => continue,
                 (_, _, Doc::Text(text, s_len, _)) if &**text == "{" => {
                     // Special case fot the embracing op
                     if let Some((_, _, next_doc)) = docs.front() {
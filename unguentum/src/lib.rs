@@ -1,8 +1,12 @@
 mod code;
+mod comments;
 pub mod config;
 mod format;
+mod pipes;
 pub(crate) mod post_format_hooks;
 pub(crate) mod pre_format_hooks;
+mod quotes;
+mod spacing;
 
 use crate::code::Code;
 use crate::format::DocBuffer;
@@ -13,6 +17,8 @@ use parser::ast::Expression;
 use post_format_hooks::trim_line_endings;
 use post_format_hooks::trim_trailing_line;
 use std::collections::VecDeque;
+use tokenizer::Token;
+use tokenizer::tokens::CommentedToken;
 
 pub fn format_code<T: config::FormattingConfig>(
     mut expression: Expression,
@@ -28,6 +34,24 @@ pub fn format_code<T: config::FormattingConfig>(
     for hook in pre_format {
         hook(&mut expression);
     }
+    // The rewritten operators are new tokens, so they need to outlive the expression
+    let replacements =
+        pre_format_hooks::assignment_replacements(&mut expression, formatting_config);
+    let pipes = if formatting_config.convert_magrittr_pipe() {
+        pre_format_hooks::pipe_replacements(&mut expression)
+    } else {
+        vec![]
+    };
+    let braces = [
+        CommentedToken::new(Token::LBrace, 0),
+        CommentedToken::new(Token::RBrace, 0),
+    ];
+    let mut expression = expression;
+    pre_format_hooks::rewrite_assignments(&mut expression, formatting_config, &replacements);
+    if formatting_config.convert_magrittr_pipe() {
+        pre_format_hooks::rewrite_magrittr_pipes(&mut expression, &pipes);
+    }
+    pre_format_hooks::enforce_braces(&mut expression, formatting_config, &braces);
 
     // Doc stage
     debug!("Transforming to docs");
@@ -45,6 +69,8 @@ pub fn format_code<T: config::FormattingConfig>(
     use std::collections::HashSet;
     let mut broken_docs = HashSet::default();
     let simple_docs = format::it_format_to_sdoc(0, &mut docs, formatting_config, &mut broken_docs);
+    let simple_docs = format::cap_blank_lines(simple_docs, formatting_config);
+    let simple_docs = comments::format_comments(simple_docs, formatting_config);
     trace!("Simple docs: {:?}", simple_docs);
 
     // Printing to string
@@ -0,0 +1,184 @@
+use parser::ast::Expression;
+use tokenizer::{Token, tokens::CommentedToken};
+
+/// The steps of a pipe chain following its first element.
+pub(crate) type PipeSteps<'e, 'a> = Vec<(&'e CommentedToken<'a>, &'e Expression<'a>)>;
+
+/// Returns whether the token is a pipe: `|>` or one of the magrittr pipes.
+pub(crate) fn is_pipe(token: &Token) -> bool {
+    matches!(
+        token,
+        Token::Pipe | Token::Special("%>%" | "%<>%" | "%T>%" | "%$%")
+    )
+}
+
+/// Returns the first element and the steps of a pipe chain,
+/// or None if the expression is not a pipe chain.
+///
+/// The parser nests the chains mixing `|>` and the magrittr pipes,
+/// because `|>` binds less tightly than `%>%` there. The chain is
+/// flattened, so every step of the source is a step of the chain.
+pub(crate) fn pipe_chain<'e, 'a>(
+    expression: &'e Expression<'a>,
+) -> Option<(&'e Expression<'a>, PipeSteps<'e, 'a>)> {
+    let (lhs, operations): (&Expression, Vec<_>) = match expression {
+        Expression::Bop(op, lhs, rhs) if is_pipe(&op.token) => (lhs, vec![(*op, &**rhs)]),
+        Expression::MultiBop(lhs, others) if others.iter().all(|(op, _)| is_pipe(&op.token)) => {
+            (lhs, others.iter().map(|(op, rhs)| (*op, &**rhs)).collect())
+        }
+        _ => return None,
+    };
+    let (first, mut steps) = pipe_chain(lhs).unwrap_or((lhs, vec![]));
+    for (op, rhs) in operations {
+        let (step, rhs_steps) = pipe_chain(rhs).unwrap_or((rhs, vec![]));
+        steps.push((op, step));
+        steps.extend(rhs_steps);
+    }
+    Some((first, steps))
+}
+
+/// Returns whether `lhs %>% step` can become `lhs |> step`
+/// without changing its meaning.
+///
+/// The native pipe needs a call on its right-hand side, other than
+/// a call of a function with special syntax, and does not know
+/// the `.` placeholder. A chain starting with `.` is a magrittr
+/// functional sequence.
+pub(crate) fn is_native_pipe_compatible(lhs: &Expression<'_>, step: &Expression<'_>) -> bool {
+    let is_placeholder = |token: &CommentedToken| matches!(token.token, Token::Symbol("."));
+    let mut is_functional_sequence = false;
+    let mut is_first = true;
+    lhs.for_each_token(&mut |token| {
+        is_functional_sequence |= is_first && is_placeholder(token);
+        is_first = false;
+    });
+    let mut has_placeholder = false;
+    step.for_each_token(&mut |token| has_placeholder |= is_placeholder(token));
+    let is_call = match step {
+        Expression::FunctionCall(call) => !is_special_function(&call.function_ref),
+        _ => false,
+    };
+    is_call && !has_placeholder && !is_functional_sequence
+}
+
+/// Returns whether the function is a backtick-quoted operator or reserved word,
+/// such as `` `[[` `` or `` `if` ``, which R rejects on the right-hand side of `|>`.
+fn is_special_function(function: &Expression<'_>) -> bool {
+    const RESERVED_WORDS: [&str; 19] = [
+        "if",
+        "else",
+        "repeat",
+        "while",
+        "function",
+        "for",
+        "in",
+        "next",
+        "break",
+        "return",
+        "TRUE",
+        "FALSE",
+        "NULL",
+        "Inf",
+        "NaN",
+        "NA",
+        "NA_integer_",
+        "NA_real_",
+        "NA_character_",
+    ];
+    let Expression::Symbol(token) = function else {
+        return false;
+    };
+    let Token::Symbol(name) = token.token else {
+        return false;
+    };
+    let Some(name) = name
+        .strip_prefix('`')
+        .and_then(|name| name.strip_suffix('`'))
+    else {
+        return false;
+    };
+    let is_operator = !name.chars().any(char::is_alphanumeric);
+    is_operator || RESERVED_WORDS.contains(&name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use parser::{Input, parse, pre_parse};
+    use tokenizer::Tokenizer;
+
+    fn with_expression(source: &str, f: impl FnOnce(&Expression)) {
+        let mut tokens = Tokenizer::new(source).tokenize().unwrap();
+        let tokens = pre_parse(&mut tokens);
+        let expressions = parse(Input(&tokens)).unwrap();
+        f(&expressions[0]);
+    }
+
+    #[test]
+    fn flattens_mixed_pipe_chains() {
+        with_expression("a %>% b() |> c() %>% d() |> e()\n", |expression| {
+            let (_, steps) = pipe_chain(expression).unwrap();
+            let ops: Vec<_> = steps.iter().map(|(op, _)| op.token.clone()).collect();
+            assert_eq!(
+                ops,
+                [
+                    Token::Special("%>%"),
+                    Token::Pipe,
+                    Token::Special("%>%"),
+                    Token::Pipe
+                ]
+            );
+        });
+    }
+
+    #[test]
+    fn other_operators_are_not_pipe_chains() {
+        for source in [
+            "a + b\n",
+            "a %in% b\n",
+            "a %>% b %in% c\n",
+            "x <- a |> b()\n",
+        ] {
+            with_expression(source, |expression| {
+                assert!(pipe_chain(expression).is_none(), "{source}");
+            });
+        }
+    }
+
+    #[test]
+    fn detects_native_pipe_compatible_steps() {
+        let examples = [
+            ("a %>% f()\n", true),
+            ("a %>% pkg::f(x)\n", true),
+            ("a %>% f(.x)\n", true),
+            ("a %>% f\n", false),
+            ("a %>% f(.)\n", false),
+            ("a %>% f(g(x = .))\n", false),
+            ("a %>% {\n  f(.)\n}\n", false),
+            (". %>% f()\n", false),
+            ("a %>% `my fun`()\n", true),
+            ("a %>% `%in%`(b)\n", true),
+            ("a %>% `f`()\n", true),
+            ("a %>% `[`(1)\n", false),
+            ("a %>% `[[`(\"a\")\n", false),
+            ("a %>% `$`(name)\n", false),
+            ("a %>% `@`(slot)\n", false),
+            ("a %>% `(`()\n", false),
+            ("a %>% `{`()\n", false),
+            ("a %>% `+`(1)\n", false),
+            ("a %>% `<-`(1)\n", false),
+            ("a %>% `if`(yes, no)\n", false),
+            ("a %>% `function`()\n", false),
+        ];
+        for (source, expected) in examples {
+            with_expression(source, |expression| {
+                let (first, steps) = pipe_chain(expression).unwrap();
+                assert_eq!(
+                    is_native_pipe_compatible(first, steps[0].1),
+                    expected,
+                    "{source}"
+                );
+            });
+        }
+    }
+}
@@ -1,4 +1,8 @@
-use parser::ast::Expression;
+use parser::ast::{Arg, Args, Expression, TermExpr};
+use tokenizer::{Token, tokens::CommentedToken};
+
+use crate::config::{AssignmentOperator, EnforceBraces, FormattingConfig};
+use crate::pipes::is_native_pipe_compatible;
 
 pub(crate) fn remove_trailing_whitespace_from_function_defs(expression: &mut Expression) {
     match expression {
@@ -6,8 +10,9 @@ pub(crate) fn remove_trailing_whitespace_from_function_defs(expression: &mut Exp
         | Expression::Literal(_)
         | Expression::Comment(_)
         | Expression::Continue(_)
-        | Expression::Formula(_, _)
+        | Expression::Formula(_)
         | Expression::Newline(_)
+        | Expression::Semicolon(_, _)
         | Expression::Whitespace(_)
         | Expression::EOF(_)
         | Expression::Break(_) => {}
@@ -30,6 +35,18 @@ pub(crate) fn remove_trailing_whitespace_from_function_defs(expression: &mut Exp
                 .map(|(_, rhs)| rhs)
                 .for_each(|arg0| remove_trailing_whitespace_from_function_defs(arg0));
         }
+        Expression::NamespaceAccess(namespace_access) => {
+            remove_trailing_whitespace_from_function_defs(&mut namespace_access.namespace);
+            remove_trailing_whitespace_from_function_defs(&mut namespace_access.name);
+        }
+        Expression::MemberAccess(member_access) => {
+            remove_trailing_whitespace_from_function_defs(&mut member_access.object);
+            remove_trailing_whitespace_from_function_defs(&mut member_access.member);
+        }
+        Expression::SlotAccess(slot_access) => {
+            remove_trailing_whitespace_from_function_defs(&mut slot_access.object);
+            remove_trailing_whitespace_from_function_defs(&mut slot_access.slot);
+        }
         Expression::FunctionDef(function_def) => {
             let body = &mut function_def.body;
             if let Expression::Term(ref mut terms) = **body {
@@ -100,3 +117,595 @@ pub(crate) fn remove_trailing_whitespace_from_function_defs(expression: &mut Exp
         }
     }
 }
+
+/// Returns the `<-` operators replacing the assignment operators
+/// rewritten by [`rewrite_assignments`].
+///
+/// The replacements keep the comments of the original operators.
+pub(crate) fn assignment_replacements<'a>(
+    expression: &mut Expression<'a>,
+    config: &impl FormattingConfig,
+) -> Vec<CommentedToken<'a>> {
+    let mut replacements = vec![];
+    visit_assignments(expression, config, false, &mut |operator| {
+        replacements.push(CommentedToken {
+            token: Token::LAssign,
+            ..operator.clone()
+        });
+        None
+    });
+    replacements
+}
+
+/// Rewrites `=` and `->` assignments into `<-` assignments,
+/// according to the config.
+///
+/// `replacements` are the operators returned by [`assignment_replacements`]
+/// for the same expression.
+pub(crate) fn rewrite_assignments<'a>(
+    expression: &mut Expression<'a>,
+    config: &impl FormattingConfig,
+    replacements: &'a [CommentedToken<'a>],
+) {
+    let mut replacements = replacements.iter();
+    visit_assignments(expression, config, false, &mut |_| replacements.next());
+}
+
+/// Visits the assignment operators to rewrite in the order of appearance
+/// of the operators in the source.
+///
+/// If `replace` returns a new operator, the assignment is rewritten
+/// to use it. `=` directly inside of the arguments of a call
+/// is a named argument, not an assignment.
+fn visit_assignments<'a, F>(
+    expression: &mut Expression<'a>,
+    config: &impl FormattingConfig,
+    is_argument: bool,
+    replace: &mut F,
+) where
+    F: FnMut(&'a CommentedToken<'a>) -> Option<&'a CommentedToken<'a>>,
+{
+    let is_rewritten = |operator: &CommentedToken| match operator.token {
+        Token::OldAssign => {
+            !is_argument && config.assignment_operator() == AssignmentOperator::LeftArrow
+        }
+        Token::RAssign => config.rewrite_right_assign(),
+        _ => false,
+    };
+    match expression {
+        Expression::Symbol(_)
+        | Expression::Literal(_)
+        | Expression::Comment(_)
+        | Expression::Continue(_)
+        | Expression::Newline(_)
+        | Expression::Semicolon(_, _)
+        | Expression::Whitespace(_)
+        | Expression::EOF(_)
+        | Expression::Break(_) => {}
+        Expression::Term(term) => term
+            .term
+            .iter_mut()
+            .for_each(|expression| visit_assignments(expression, config, false, replace)),
+        Expression::Unary(_, expression) => visit_assignments(expression, config, false, replace),
+        Expression::Bop(operator, lhs, rhs) => {
+            visit_assignments(lhs, config, false, replace);
+            visit_assignments(rhs, config, false, replace);
+            if is_rewritten(operator) {
+                if let Some(new_operator) = replace(operator) {
+                    if matches!(operator.token, Token::RAssign) {
+                        std::mem::swap(lhs, rhs);
+                    }
+                    *operator = new_operator;
+                }
+            }
+        }
+        Expression::MultiBop(lhs, others) => {
+            visit_assignments(lhs, config, false, replace);
+            for (_, rhs) in others.iter_mut() {
+                visit_assignments(rhs, config, false, replace);
+            }
+            let mut is_rewritten_right_assignment = false;
+            for (operator, _) in others.iter_mut() {
+                if is_rewritten(operator) {
+                    if let Some(new_operator) = replace(operator) {
+                        is_rewritten_right_assignment = matches!(operator.token, Token::RAssign);
+                        *operator = new_operator;
+                    }
+                }
+            }
+            // The operators have the same precedence, so all of them are right
+            // assignments. a -> b -> c becomes c <- b <- a
+            if is_rewritten_right_assignment {
+                let (mut operators, mut operands): (Vec<_>, Vec<_>) =
+                    std::mem::take(others).into_iter().unzip();
+                let last = operands.len() - 1;
+                std::mem::swap(lhs, &mut operands[last]);
+                operands[..last].reverse();
+                operators.reverse();
+                *others = operators.into_iter().zip(operands).collect();
+            }
+        }
+        Expression::Formula(formula) => {
+            if let Some(lhs) = formula.lhs.as_mut() {
+                visit_assignments(lhs, config, false, replace);
+            }
+            visit_assignments(&mut formula.rhs, config, false, replace);
+        }
+        Expression::NamespaceAccess(namespace_access) => {
+            visit_assignments(&mut namespace_access.namespace, config, false, replace);
+            visit_assignments(&mut namespace_access.name, config, false, replace);
+        }
+        Expression::MemberAccess(member_access) => {
+            visit_assignments(&mut member_access.object, config, false, replace);
+            visit_assignments(&mut member_access.member, config, false, replace);
+        }
+        Expression::SlotAccess(slot_access) => {
+            visit_assignments(&mut slot_access.object, config, false, replace);
+            visit_assignments(&mut slot_access.slot, config, false, replace);
+        }
+        Expression::FunctionDef(function_def) => {
+            visit_assignments_in_args(&mut function_def.arguments, config, replace);
+            visit_assignments(&mut function_def.body, config, false, replace);
+        }
+        Expression::LambdaFunction(lambda) => {
+            visit_assignments_in_args(&mut lambda.args, config, replace);
+            visit_assignments(&mut lambda.body, config, false, replace);
+        }
+        Expression::IfExpression(if_expression) => {
+            let conditionals = std::iter::once(&mut if_expression.if_conditional).chain(
+                if_expression
+                    .else_ifs
+                    .iter_mut()
+                    .map(|else_if| &mut else_if.if_conditional),
+            );
+            for conditional in conditionals {
+                visit_assignments(&mut conditional.condition, config, false, replace);
+                visit_assignments(&mut conditional.body, config, false, replace);
+            }
+            if let Some(trailing_else) = if_expression.trailing_else.as_mut() {
+                visit_assignments(&mut trailing_else.body, config, false, replace);
+            }
+        }
+        Expression::WhileExpression(while_loop) => {
+            visit_assignments(&mut while_loop.condition, config, false, replace);
+            visit_assignments(&mut while_loop.body, config, false, replace);
+        }
+        Expression::RepeatExpression(repeat_loop) => {
+            visit_assignments(&mut repeat_loop.body, config, false, replace);
+        }
+        Expression::FunctionCall(call) => {
+            visit_assignments(&mut call.function_ref, config, false, replace);
+            visit_assignments_in_args(&mut call.args, config, replace);
+        }
+        Expression::SubsetExpression(subset) => {
+            visit_assignments(&mut subset.object_ref, config, false, replace);
+            visit_assignments_in_args(&mut subset.args, config, replace);
+        }
+        Expression::ForLoopExpression(for_loop) => {
+            visit_assignments(&mut for_loop.identifier, config, false, replace);
+            visit_assignments(&mut for_loop.collection, config, false, replace);
+            visit_assignments(&mut for_loop.body, config, false, replace);
+        }
+    }
+}
+
+fn visit_assignments_in_args<'a, F>(
+    args: &mut Args<'a>,
+    config: &impl FormattingConfig,
+    replace: &mut F,
+) where
+    F: FnMut(&'a CommentedToken<'a>) -> Option<&'a CommentedToken<'a>>,
+{
+    for arg in args.args.iter_mut() {
+        match arg {
+            Arg::Proper(Some(expression), _) => {
+                visit_assignments(expression, config, true, replace)
+            }
+            Arg::Proper(None, _) => {}
+            Arg::EmptyEqual(name, _, _) => visit_assignments(name, config, true, replace),
+        }
+    }
+}
+
+/// Returns the `|>` operators replacing the `%>%` operators
+/// rewritten by [`rewrite_magrittr_pipes`].
+///
+/// The replacements keep the comments of the original operators.
+pub(crate) fn pipe_replacements<'a>(expression: &mut Expression<'a>) -> Vec<CommentedToken<'a>> {
+    let mut replacements = vec![];
+    visit_magrittr_pipes(expression, &mut |operator| {
+        replacements.push(CommentedToken {
+            token: Token::Pipe,
+            ..operator.clone()
+        });
+        None
+    });
+    replacements
+}
+
+/// Rewrites the `%>%` operators into `|>` where the native pipe
+/// behaves the same.
+///
+/// `replacements` are the operators returned by [`pipe_replacements`]
+/// for the same expression.
+pub(crate) fn rewrite_magrittr_pipes<'a>(
+    expression: &mut Expression<'a>,
+    replacements: &'a [CommentedToken<'a>],
+) {
+    let mut replacements = replacements.iter();
+    visit_magrittr_pipes(expression, &mut |_| replacements.next());
+}
+
+/// Visits the `%>%` operators that can become `|>` in the order
+/// of appearance of the operators in the source.
+///
+/// If `replace` returns a new operator, the pipe is rewritten to use it.
+fn visit_magrittr_pipes<'a, F>(expression: &mut Expression<'a>, replace: &mut F)
+where
+    F: FnMut(&'a CommentedToken<'a>) -> Option<&'a CommentedToken<'a>>,
+{
+    let is_magrittr_pipe = |operator: &CommentedToken| operator.token == Token::Special("%>%");
+    match expression {
+        Expression::Symbol(_)
+        | Expression::Literal(_)
+        | Expression::Comment(_)
+        | Expression::Continue(_)
+        | Expression::Newline(_)
+        | Expression::Semicolon(_, _)
+        | Expression::Whitespace(_)
+        | Expression::EOF(_)
+        | Expression::Break(_) => {}
+        Expression::Term(term) => term
+            .term
+            .iter_mut()
+            .for_each(|expression| visit_magrittr_pipes(expression, replace)),
+        Expression::Unary(_, expression) => visit_magrittr_pipes(expression, replace),
+        Expression::Bop(operator, lhs, rhs) => {
+            visit_magrittr_pipes(lhs, replace);
+            visit_magrittr_pipes(rhs, replace);
+            if is_magrittr_pipe(operator) && is_native_pipe_compatible(lhs, rhs) {
+                if let Some(new_operator) = replace(operator) {
+                    *operator = new_operator;
+                }
+            }
+        }
+        Expression::MultiBop(lhs, others) => {
+            visit_magrittr_pipes(lhs, replace);
+            for (_, rhs) in others.iter_mut() {
+                visit_magrittr_pipes(rhs, replace);
+            }
+            for (operator, rhs) in others.iter_mut() {
+                if is_magrittr_pipe(operator) && is_native_pipe_compatible(lhs, rhs) {
+                    if let Some(new_operator) = replace(operator) {
+                        *operator = new_operator;
+                    }
+                }
+            }
+        }
+        Expression::Formula(formula) => {
+            if let Some(lhs) = formula.lhs.as_mut() {
+                visit_magrittr_pipes(lhs, replace);
+            }
+            visit_magrittr_pipes(&mut formula.rhs, replace);
+        }
+        Expression::NamespaceAccess(namespace_access) => {
+            visit_magrittr_pipes(&mut namespace_access.namespace, replace);
+            visit_magrittr_pipes(&mut namespace_access.name, replace);
+        }
+        Expression::MemberAccess(member_access) => {
+            visit_magrittr_pipes(&mut member_access.object, replace);
+            visit_magrittr_pipes(&mut member_access.member, replace);
+        }
+        Expression::SlotAccess(slot_access) => {
+            visit_magrittr_pipes(&mut slot_access.object, replace);
+            visit_magrittr_pipes(&mut slot_access.slot, replace);
+        }
+        Expression::FunctionDef(function_def) => {
+            visit_magrittr_pipes_in_args(&mut function_def.arguments, replace);
+            visit_magrittr_pipes(&mut function_def.body, replace);
+        }
+        Expression::LambdaFunction(lambda) => {
+            visit_magrittr_pipes_in_args(&mut lambda.args, replace);
+            visit_magrittr_pipes(&mut lambda.body, replace);
+        }
+        Expression::IfExpression(if_expression) => {
+            let conditionals = std::iter::once(&mut if_expression.if_conditional).chain(
+                if_expression
+                    .else_ifs
+                    .iter_mut()
+                    .map(|else_if| &mut else_if.if_conditional),
+            );
+            for conditional in conditionals {
+                visit_magrittr_pipes(&mut conditional.condition, replace);
+                visit_magrittr_pipes(&mut conditional.body, replace);
+            }
+            if let Some(trailing_else) = if_expression.trailing_else.as_mut() {
+                visit_magrittr_pipes(&mut trailing_else.body, replace);
+            }
+        }
+        Expression::WhileExpression(while_loop) => {
+            visit_magrittr_pipes(&mut while_loop.condition, replace);
+            visit_magrittr_pipes(&mut while_loop.body, replace);
+        }
+        Expression::RepeatExpression(repeat_loop) => {
+            visit_magrittr_pipes(&mut repeat_loop.body, replace);
+        }
+        Expression::FunctionCall(call) => {
+            visit_magrittr_pipes(&mut call.function_ref, replace);
+            visit_magrittr_pipes_in_args(&mut call.args, replace);
+        }
+        Expression::SubsetExpression(subset) => {
+            visit_magrittr_pipes(&mut subset.object_ref, replace);
+            visit_magrittr_pipes_in_args(&mut subset.args, replace);
+        }
+        Expression::ForLoopExpression(for_loop) => {
+            visit_magrittr_pipes(&mut for_loop.identifier, replace);
+            visit_magrittr_pipes(&mut for_loop.collection, replace);
+            visit_magrittr_pipes(&mut for_loop.body, replace);
+        }
+    }
+}
+
+fn visit_magrittr_pipes_in_args<'a, F>(args: &mut Args<'a>, replace: &mut F)
+where
+    F: FnMut(&'a CommentedToken<'a>) -> Option<&'a CommentedToken<'a>>,
+{
+    for arg in args.args.iter_mut() {
+        match arg {
+            Arg::Proper(Some(expression), _) => visit_magrittr_pipes(expression, replace),
+            Arg::Proper(None, _) => {}
+            Arg::EmptyEqual(name, _, _) => visit_magrittr_pipes(name, replace),
+        }
+    }
+}
+
+/// Adds or removes the braces around the bodies of `if`, `for` and `while`,
+/// according to the config.
+///
+/// `braces` are the `{` and `}` tokens of the added braces. A body keeps
+/// its braces if any of its tokens is commented, and no braces are added
+/// after a header ending with an inline comment.
+pub(crate) fn enforce_braces<'a>(
+    expression: &mut Expression<'a>,
+    config: &impl FormattingConfig,
+    braces: &'a [CommentedToken<'a>; 2],
+) {
+    match expression {
+        Expression::Symbol(_)
+        | Expression::Literal(_)
+        | Expression::Comment(_)
+        | Expression::Continue(_)
+        | Expression::Newline(_)
+        | Expression::Semicolon(_, _)
+        | Expression::Whitespace(_)
+        | Expression::EOF(_)
+        | Expression::Break(_) => {}
+        Expression::Term(term) => term
+            .term
+            .iter_mut()
+            .for_each(|expression| enforce_braces(expression, config, braces)),
+        Expression::Unary(_, expression) => enforce_braces(expression, config, braces),
+        Expression::Bop(_, lhs, rhs) => {
+            enforce_braces(lhs, config, braces);
+            enforce_braces(rhs, config, braces);
+        }
+        Expression::MultiBop(lhs, others) => {
+            enforce_braces(lhs, config, braces);
+            for (_, rhs) in others.iter_mut() {
+                enforce_braces(rhs, config, braces);
+            }
+        }
+        Expression::Formula(formula) => {
+            if let Some(lhs) = formula.lhs.as_mut() {
+                enforce_braces(lhs, config, braces);
+            }
+            enforce_braces(&mut formula.rhs, config, braces);
+        }
+        Expression::NamespaceAccess(namespace_access) => {
+            enforce_braces(&mut namespace_access.namespace, config, braces);
+            enforce_braces(&mut namespace_access.name, config, braces);
+        }
+        Expression::MemberAccess(member_access) => {
+            enforce_braces(&mut member_access.object, config, braces);
+            enforce_braces(&mut member_access.member, config, braces);
+        }
+        Expression::SlotAccess(slot_access) => {
+            enforce_braces(&mut slot_access.object, config, braces);
+            enforce_braces(&mut slot_access.slot, config, braces);
+        }
+        Expression::FunctionDef(function_def) => {
+            enforce_braces_in_args(&mut function_def.arguments, config, braces);
+            enforce_braces(&mut function_def.body, config, braces);
+        }
+        Expression::LambdaFunction(lambda) => {
+            enforce_braces_in_args(&mut lambda.args, config, braces);
+            enforce_braces(&mut lambda.body, config, braces);
+        }
+        Expression::IfExpression(if_expression) => {
+            let conditionals = std::iter::once(&mut if_expression.if_conditional).chain(
+                if_expression
+                    .else_ifs
+                    .iter_mut()
+                    .map(|else_if| &mut else_if.if_conditional),
+            );
+            let mut bodies = vec![];
+            for conditional in conditionals {
+                enforce_braces(&mut conditional.condition, config, braces);
+                bodies.push((conditional.right_delimiter, &mut conditional.body));
+            }
+            let has_else = if let Some(trailing_else) = if_expression.trailing_else.as_mut() {
+                bodies.push((trailing_else.else_keyword, &mut trailing_else.body));
+                true
+            } else {
+                false
+            };
+            for (_, body) in bodies.iter_mut() {
+                enforce_braces(body, config, braces);
+            }
+            if !has_else
+                && bodies.len() == 1
+                && config.remove_redundant_braces()
+                && config.enforce_braces() != EnforceBraces::Always
+            {
+                remove_braces(bodies[0].1);
+            }
+            // All the branches of an if expression have braces if any of them does
+            let is_braced = match config.enforce_braces() {
+                EnforceBraces::Always => true,
+                EnforceBraces::Multiline => bodies
+                    .iter()
+                    .any(|(_, body)| is_braced(body) || spans_multiple_lines(body)),
+                EnforceBraces::Preserve => false,
+            };
+            if is_braced {
+                for (header_end, body) in bodies {
+                    add_braces(header_end, body, braces);
+                }
+            }
+        }
+        Expression::WhileExpression(while_loop) => {
+            enforce_braces(&mut while_loop.condition, config, braces);
+            enforce_braces(&mut while_loop.body, config, braces);
+            let mut header_end = None;
+            while_loop
+                .condition
+                .for_each_token(&mut |token| header_end = Some(token));
+            if let Some(header_end) = header_end {
+                enforce_loop_braces(header_end, &mut while_loop.body, config, braces);
+            }
+        }
+        Expression::RepeatExpression(repeat_loop) => {
+            enforce_braces(&mut repeat_loop.body, config, braces);
+        }
+        Expression::FunctionCall(call) => {
+            enforce_braces(&mut call.function_ref, config, braces);
+            enforce_braces_in_args(&mut call.args, config, braces);
+        }
+        Expression::SubsetExpression(subset) => {
+            enforce_braces(&mut subset.object_ref, config, braces);
+            enforce_braces_in_args(&mut subset.args, config, braces);
+        }
+        Expression::ForLoopExpression(for_loop) => {
+            enforce_braces(&mut for_loop.identifier, config, braces);
+            enforce_braces(&mut for_loop.collection, config, braces);
+            enforce_braces(&mut for_loop.body, config, braces);
+            let mut header_end = None;
+            for_loop
+                .right_delim
+                .for_each_token(&mut |token| header_end = Some(token));
+            if let Some(header_end) = header_end {
+                enforce_loop_braces(header_end, &mut for_loop.body, config, braces);
+            }
+        }
+    }
+}
+
+fn enforce_braces_in_args<'a>(
+    args: &mut Args<'a>,
+    config: &impl FormattingConfig,
+    braces: &'a [CommentedToken<'a>; 2],
+) {
+    for arg in args.args.iter_mut() {
+        match arg {
+            Arg::Proper(Some(expression), _) => enforce_braces(expression, config, braces),
+            Arg::Proper(None, _) => {}
+            Arg::EmptyEqual(name, _, _) => enforce_braces(name, config, braces),
+        }
+    }
+}
+
+fn enforce_loop_braces<'a>(
+    header_end: &CommentedToken<'_>,
+    body: &mut Box<Expression<'a>>,
+    config: &impl FormattingConfig,
+    braces: &'a [CommentedToken<'a>; 2],
+) {
+    let is_braced = match config.enforce_braces() {
+        EnforceBraces::Always => true,
+        EnforceBraces::Multiline => spans_multiple_lines(body),
+        EnforceBraces::Preserve => false,
+    };
+    if is_braced {
+        add_braces(header_end, body, braces);
+    }
+}
+
+/// Wraps the body in braces unless it already has them.
+fn add_braces<'a>(
+    header_end: &CommentedToken<'_>,
+    body: &mut Box<Expression<'a>>,
+    braces: &'a [CommentedToken<'a>; 2],
+) {
+    if is_braced(body) || header_end.inline_comment.is_some() {
+        return;
+    }
+    let [left_brace, right_brace] = braces;
+    let statement = std::mem::replace(
+        &mut **body,
+        Expression::Term(Box::new(TermExpr::new(None, vec![], None))),
+    );
+    **body = Expression::Term(Box::new(TermExpr::new(
+        Some(left_brace),
+        vec![statement],
+        Some(right_brace),
+    )));
+}
+
+/// Unwraps a body in braces holding a single statement
+/// that fits on one line.
+fn remove_braces(body: &mut Box<Expression<'_>>) {
+    if !is_braced(body) {
+        return;
+    }
+    let Expression::Term(term) = &**body else {
+        return;
+    };
+    let is_commented = |token: Option<&CommentedToken>| {
+        token
+            .is_some_and(|token| token.leading_comments.is_some() || token.inline_comment.is_some())
+    };
+    if is_commented(term.pre_delimiters) || is_commented(term.post_delimiters) {
+        return;
+    }
+    let mut statements = term.term.iter().filter(|statement| {
+        !matches!(
+            statement,
+            Expression::Whitespace(_) | Expression::Newline(_)
+        )
+    });
+    let (Some(statement), None) = (statements.next(), statements.next()) else {
+        return;
+    };
+    if spans_multiple_lines(statement) || matches!(statement, Expression::Comment(_)) {
+        return;
+    }
+    **body = statement.clone();
+}
+
+fn is_braced(body: &Expression<'_>) -> bool {
+    matches!(body, Expression::Term(term)
+        if term.pre_delimiters.is_some_and(|delim| matches!(delim.token, Token::LBrace)))
+}
+
+/// Whether the expression is printed on multiple lines regardless
+/// of the line length.
+///
+/// Such expressions contain braces, comments or control flow.
+fn spans_multiple_lines(expression: &Expression<'_>) -> bool {
+    if matches!(
+        expression,
+        Expression::IfExpression(_)
+            | Expression::ForLoopExpression(_)
+            | Expression::WhileExpression(_)
+            | Expression::RepeatExpression(_)
+    ) {
+        return true;
+    }
+    let mut spans_multiple_lines = false;
+    expression.for_each_token(&mut |token| {
+        spans_multiple_lines |= matches!(token.token, Token::LBrace)
+            || token.leading_comments.is_some()
+            || token.inline_comment.is_some();
+    });
+    spans_multiple_lines
+}
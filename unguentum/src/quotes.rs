@@ -0,0 +1,164 @@
+use std::borrow::Cow;
+
+use crate::config::QuoteStyle;
+
+/// Rewrites the delimiters of a string literal to match the quote style.
+///
+/// The escapes of the old delimiter are removed. The literal stays
+/// as it is if it contains the new delimiter, so no new escapes are
+/// ever needed. Other literals, such as numbers and raw strings,
+/// are returned unchanged.
+pub(crate) fn normalise_quotes(literal: &str, quote_style: QuoteStyle) -> Cow<'_, str> {
+    let (delimiter, other) = match quote_style {
+        QuoteStyle::Preserve => return Cow::Borrowed(literal),
+        QuoteStyle::Double => ('"', '\''),
+        QuoteStyle::Single => ('\'', '"'),
+    };
+    let Some(content) = literal
+        .strip_prefix(other)
+        .and_then(|literal| literal.strip_suffix(other))
+    else {
+        return Cow::Borrowed(literal);
+    };
+
+    let mut rewritten = String::with_capacity(literal.len());
+    rewritten.push(delimiter);
+    let mut chars = content.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some(escaped) if escaped == other => rewritten.push(escaped),
+                Some(escaped) => {
+                    rewritten.push('\\');
+                    rewritten.push(escaped);
+                }
+                // The closing delimiter is escaped, so the literal is not terminated
+                None => return Cow::Borrowed(literal),
+            },
+            c if c == delimiter => return Cow::Borrowed(literal),
+            // An unescaped delimiter means the literal is not a single string
+            c if c == other => return Cow::Borrowed(literal),
+            c => rewritten.push(c),
+        }
+    }
+    rewritten.push(delimiter);
+    Cow::Owned(rewritten)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn preserve_keeps_the_literal() {
+        for literal in ["'a'", "\"a\"", "'it\\'s'", "r\"(a)\""] {
+            assert_eq!(normalise_quotes(literal, QuoteStyle::Preserve), literal);
+        }
+    }
+
+    #[test]
+    fn rewrites_the_delimiters() {
+        let examples = [
+            ("'a'", "\"a\""),
+            ("''", "\"\""),
+            ("'multi\nline'", "\"multi\nline\""),
+            ("'ünïcödé'", "\"ünïcödé\""),
+            ("\"a\"", "\"a\""),
+        ];
+        for (literal, expected) in examples {
+            assert_eq!(normalise_quotes(literal, QuoteStyle::Double), expected);
+        }
+        let examples = [("\"a\"", "'a'"), ("\"\"", "''"), ("'a'", "'a'")];
+        for (literal, expected) in examples {
+            assert_eq!(normalise_quotes(literal, QuoteStyle::Single), expected);
+        }
+    }
+
+    #[test]
+    fn keeps_escape_sequences() {
+        let escapes = [
+            "\\n",
+            "\\r",
+            "\\t",
+            "\\b",
+            "\\a",
+            "\\f",
+            "\\v",
+            "\\\\",
+            "\\0",
+            "\\123",
+            "\\x4F",
+            "\\u00e9",
+            "\\u{00e9}",
+            "\\U0001F600",
+            "\\U{0001F600}",
+            "\\`",
+            "\\ ",
+        ];
+        for escape in escapes {
+            assert_eq!(
+                normalise_quotes(&format!("'{escape}'"), QuoteStyle::Double),
+                format!("\"{escape}\"")
+            );
+            assert_eq!(
+                normalise_quotes(&format!("\"{escape}\""), QuoteStyle::Single),
+                format!("'{escape}'")
+            );
+        }
+    }
+
+    #[test]
+    fn removes_escapes_of_the_old_delimiter() {
+        assert_eq!(normalise_quotes("'it\\'s'", QuoteStyle::Double), "\"it's\"");
+        assert_eq!(
+            normalise_quotes("\"say \\\"hi\\\"\"", QuoteStyle::Single),
+            "'say \"hi\"'"
+        );
+        assert_eq!(
+            normalise_quotes("'\\\\\\''", QuoteStyle::Double),
+            "\"\\\\'\""
+        );
+    }
+
+    #[test]
+    fn keeps_escaped_new_delimiters() {
+        assert_eq!(
+            normalise_quotes("'a \\\" b'", QuoteStyle::Double),
+            "\"a \\\" b\""
+        );
+        assert_eq!(
+            normalise_quotes("\"a \\' b\"", QuoteStyle::Single),
+            "'a \\' b'"
+        );
+    }
+
+    #[test]
+    fn keeps_literals_containing_the_new_delimiter() {
+        assert_eq!(
+            normalise_quotes("'say \"hi\"'", QuoteStyle::Double),
+            "'say \"hi\"'"
+        );
+        assert_eq!(normalise_quotes("\"it's\"", QuoteStyle::Single), "\"it's\"");
+    }
+
+    #[test]
+    fn keeps_other_literals() {
+        let literals = [
+            "1",
+            "1L",
+            "0x1F",
+            "TRUE",
+            "NULL",
+            "r\"(a)\"",
+            "R'[a]'",
+            "r\"---{a}---\"",
+            "'unterminated",
+            "'\\'",
+            "'",
+        ];
+        for literal in literals {
+            assert_eq!(normalise_quotes(literal, QuoteStyle::Double), literal);
+            assert_eq!(normalise_quotes(literal, QuoteStyle::Single), literal);
+        }
+    }
+}
@@ -0,0 +1,82 @@
+use tokenizer::Token;
+
+use crate::config::FormattingConfig;
+
+/// Returns whether the binary operator is surrounded by spaces,
+/// or None if the token is not a binary operator.
+///
+/// This is the table of the spacing of all binary operators.
+/// The operators which are not configurable are always or never
+/// surrounded by spaces. The accessors, such as `$`, `@` and `::`,
+/// are not binary operators.
+pub(crate) fn spaces_around_operator(op: &Token, config: &impl FormattingConfig) -> Option<bool> {
+    let spaces = match op {
+        Token::Power => config.spaces_around_power(),
+        Token::Divide => config.spaces_around_slash(),
+        // The magrittr pipes are always spaced, like `|>`
+        Token::Special("%>%" | "%<>%" | "%T>%" | "%$%") => true,
+        Token::Special(_) => config.space_in_special_ops(),
+        Token::Colon | Token::Help => false,
+        Token::OldAssign
+        | Token::LAssign
+        | Token::ColonAssign
+        | Token::SuperAssign
+        | Token::RAssign
+        | Token::Equal
+        | Token::NotEqual
+        | Token::LowerThan
+        | Token::GreaterThan
+        | Token::LowerEqual
+        | Token::GreaterEqual
+        | Token::Multiply
+        | Token::Minus
+        | Token::Plus
+        | Token::And
+        | Token::VectorizedAnd
+        | Token::Or
+        | Token::VectorizedOr
+        | Token::Pipe
+        | Token::Modulo => true,
+        _ => return None,
+    };
+    Some(spaces)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{Config, SpaceInSpecialOps, SpacesAroundPower, SpacesAroundSlash};
+
+    #[test]
+    fn default_spacing() {
+        let config = Config::default();
+        assert_eq!(spaces_around_operator(&Token::Plus, &config), Some(true));
+        assert_eq!(spaces_around_operator(&Token::LAssign, &config), Some(true));
+        assert_eq!(spaces_around_operator(&Token::Divide, &config), Some(true));
+        assert_eq!(
+            spaces_around_operator(&Token::Special("%in%"), &config),
+            Some(true)
+        );
+        assert_eq!(spaces_around_operator(&Token::Power, &config), Some(false));
+        assert_eq!(spaces_around_operator(&Token::Colon, &config), Some(false));
+        assert_eq!(spaces_around_operator(&Token::Dollar, &config), None);
+        assert_eq!(spaces_around_operator(&Token::LParen, &config), None);
+    }
+
+    #[test]
+    fn configured_spacing() {
+        let config = Config {
+            spaces_around_power: SpacesAroundPower(true),
+            spaces_around_slash: SpacesAroundSlash(false),
+            space_in_special_ops: SpaceInSpecialOps(false),
+            ..Default::default()
+        };
+        assert_eq!(spaces_around_operator(&Token::Power, &config), Some(true));
+        assert_eq!(spaces_around_operator(&Token::Divide, &config), Some(false));
+        assert_eq!(
+            spaces_around_operator(&Token::Special("%in%"), &config),
+            Some(false)
+        );
+        assert_eq!(spaces_around_operator(&Token::Plus, &config), Some(true));
+    }
+}
@@ -1,4 +1,7 @@
-use tergo_formatter::config::{Config, FunctionLineBreaks};
+use tergo_formatter::config::{
+    AssignmentOperator, BraceStyle, CallArgumentsLayout, Config, EndOfLine, EnforceBraces,
+    FunctionLineBreaks, QuoteStyle, SemicolonStatements, Style,
+};
 
 fn log_init() {
     match simple_logger::init_with_env() {
@@ -23,7 +26,34 @@ fn parses_the_fully_specified_config() {
     assert!(config.strip_suffix_whitespace_in_function_defs.0);
     assert!(config.function_line_breaks == FunctionLineBreaks::Double);
     assert!(config.insert_newline_in_quote_call.0);
+    assert!(config.semicolon_statements == SemicolonStatements::Inline);
+    assert!(config.assignment_operator == AssignmentOperator::LeftArrow);
+    assert!(config.rewrite_right_assign.0);
+    assert!(config.quote_style == QuoteStyle::Double);
+    assert!(config.max_blank_lines.0 == 2);
+    assert!(config.max_blank_lines_top_level.0 == 3);
+    assert!(config.blank_lines_around_functions.0 == 1);
+    assert!(config.call_arguments_layout == CallArgumentsLayout::OnePerLine);
+    assert!(config.align_assignments.0);
+    assert!(config.align_named_arguments.0);
+    assert!(config.spaces_around_power.0);
+    assert!(!config.spaces_around_slash.0);
+    assert!(!config.space_in_special_ops.0);
+    assert!(config.brace_style == BraceStyle::Allman);
+    assert!(config.else_on_new_line.0);
+    assert!(config.function_brace_on_new_line.0);
+    assert!(config.enforce_braces == EnforceBraces::Multiline);
+    assert!(config.remove_redundant_braces.0);
+    assert_eq!(config.max_pipe_steps_on_one_line.0, Some(2));
+    assert!(config.convert_magrittr_pipe.0);
+    assert!(config.space_after_comment_hash.0);
+    assert!(config.reflow_comments.0);
+    assert!(config.format_roxygen.0);
+    assert!(config.end_of_line == EndOfLine::Crlf);
+    assert!(!config.insert_final_newline.0);
     assert!(config.exclusion_list.0.is_empty());
+    assert_eq!(config.include.0, [".Rprofile", "Rprofile.site"]);
+    assert!(config.include_shebang_scripts.0);
 }
 
 #[test]
@@ -34,3 +64,47 @@ fn parses_the_partial_config() {
 
     assert!(config.is_ok(), "Error was {config:?}");
 }
+
+#[test]
+fn the_keys_override_the_style_preset() {
+    log_init();
+    let preset_spec = include_str!("./config_cases/preset_spec.toml");
+    let config: Config = toml::from_str(preset_spec).unwrap();
+    let preset = Config::from_style(Style::Bioconductor);
+
+    assert_eq!(preset.indent.0, 4);
+    assert_eq!(config.indent.0, 2);
+    assert_eq!(config.line_length.0, preset.line_length.0);
+    assert!(config.function_line_breaks == preset.function_line_breaks);
+    assert!(config.enforce_braces == EnforceBraces::Preserve);
+}
+
+#[test]
+fn rejects_an_unknown_style() {
+    log_init();
+    let config: Result<Config, _> = toml::from_str("style = \"unknown\"");
+
+    assert!(config.is_err());
+}
+
+#[test]
+fn the_include_list_matches_extensions_and_file_names() {
+    log_init();
+    let config: Config = toml::from_str(r#"include = [".Rprofile", "Rprofile.site"]"#).unwrap();
+    assert!(config.include.matches(".Rprofile"));
+    assert!(config.include.matches("project.Rprofile"));
+    assert!(config.include.matches("Rprofile.site"));
+    assert!(!config.include.matches("other.site"));
+    assert!(!config.include.matches("script.R"));
+}
+
+#[test]
+fn displays_all_the_resolved_options() {
+    log_init();
+    let config: Config = toml::from_str("style = \"bioconductor\"").unwrap();
+    let displayed = config.to_string();
+
+    assert!(displayed.starts_with("indent: Indent(4) line_length: LineLength(80)"));
+    assert!(displayed.contains("function_line_breaks: "));
+    assert!(displayed.ends_with("include_shebang_scripts: IncludeShebangScripts(false)"));
+}